@@ -0,0 +1,35 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Generates `builtin_icon` from the icons named in assets/kinds.json, so the embedded icons
+/// always match the built-in manifest and a missing icon fails the build.
+fn main() {
+    println!("cargo:rerun-if-changed=assets/kinds.json");
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("Cargo sets CARGO_MANIFEST_DIR");
+    let assets = Path::new(&manifest_dir).join("assets");
+    let manifest = fs::read_to_string(assets.join("kinds.json")).expect("Unable to read assets/kinds.json");
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).expect("assets/kinds.json is not valid JSON");
+
+    let icons: BTreeSet<&str> = manifest["kinds"].as_array()
+        .expect("assets/kinds.json has no kinds list")
+        .iter()
+        .map(|kind| kind["icon"].as_str().expect("Every built-in kind needs an icon"))
+        .collect();
+
+    let mut arms = String::new();
+    for icon in icons {
+        let path = assets.join(icon);
+        assert!(path.is_file(), "Icon {} of the built-in kind manifest does not exist", path.display());
+        println!("cargo:rerun-if-changed={}", path.display());
+        arms.push_str(&format!("        {:?} => egui::include_image!({:?}),\n", icon, path.display().to_string()));
+    }
+
+    let source = format!(
+        "/// Icons that ship inside the binary, keyed by their path under `assets`.\nfn builtin_icon(path: &str) -> Option<ImageSource<'static>> {{\n    let source = match path {{\n{}        _ => return None\n    }};\n\n    Some(source)\n}}\n",
+        arms
+    );
+    let out_dir = std::env::var("OUT_DIR").expect("Cargo sets OUT_DIR");
+    fs::write(Path::new(&out_dir).join("builtin_icons.rs"), source).expect("Unable to write builtin_icons.rs");
+}
@@ -1,207 +1,704 @@
-/*
-use std::collections::HashMap;
-pub use client::builder::ClientBuilder;
-pub use result::WebSocketResult;
-
-use crate::map_element;
-use map_element::*;
-
-const map_element_to_sram: HashMap<MapElementKind, [[u32; 2]; 2]> = HashMap::from([
-    (MapElementKind::SUPERBUNNYU, [[0x1F0, 0x30]]),
-    (MapElementKind::SPIRALU, [[0x1FC, 0x10]]),
-    (MapElementKind::SPECM, [[0x1D5, 0x04]]),
-    (MapElementKind::PARAM, [[0x1DE, 0xF0], [0x1DF, 0x01]]),
-    (MapElementKind::PARAL, [[0x1FE, 0x30]]),
-    (MapElementKind::HOOKSHOT, [[0x078, 0xF0]]),
-    (MapElementKind::HYPE, [[0x23C, 0xF0], [0x23D, 0x04]]),
-    (MapElementKind::MIRESHED, [[0x21A, 0x30]]),
-    (MapElementKind::LIBRARY, [[0x410, 0x80]]),
-    (MapElementKind::SICKKID, [[0x410, 0x04]]),
-    (MapElementKind::AGINAH, [[0x214, 0x10]]),
-    (MapElementKind::DAM, [[0x216, 0x10]]),
-    (MapElementKind::ICEROD, [[0x240, 0x10]]),
-    (MapElementKind::HAMMERPEGS, [[0x24F, 0x04]]),
-    (MapElementKind::MMC, [[0x246, 0xF0], [0x247, 0x04]]),
-    (MapElementKind::WATERFALL, [[0x228, 0x30]]),
-    (MapElementKind::PFAIRY, [[0x22C, 0x30]]),
-    (MapElementKind::MIMIC, [[0x218, 0x10]]),
-    (MapElementKind::CHESTGAME, [[0x20D, 0x04]]),
-    (MapElementKind::CHICKEN, [[0x210, 0x10]]),
-    (MapElementKind::BONK, [[0x248, 0x10]]),
-    (MapElementKind::BREWERY, [[0x20C, 0x10]]),
-    (MapElementKind::CHECKERBOARD, [[0x24D, 0x02]]),
-    (MapElementKind::BLINDS, [[0x23A, 0xF0], [0x23B, 0x01]]),
-    (MapElementKind::SPIKE, [[0x22E, 0x10]]),
-    (MapElementKind::CAVE45, [[0x237, 0x04]]),
-    (MapElementKind::GYL, [[0x237, 0x02]]),
-    (MapElementKind::CHOUSE, [[0x238, 0x10]]),
-    (MapElementKind::KINGSTOMB, [[0x226, 0x10]]),
-    (MapElementKind::SAHA, [[0x20A, 0x70], [0x410, 0x10]]),
-    (MapElementKind::POTION, [[0x411, 0x20]]),
-    (MapElementKind::SMITH, [[0x411, 0x04]]),
-    (MapElementKind::POWDER, [[0x411, 0x80]]),
-    (MapElementKind::BOMBOSTABLET, [[0x411, 0x02]]),
-    (MapElementKind::ETHERTABLET, [[0x411, 0x01]]),
-    (MapElementKind::BOTTLEMERCHANT, [[0x3C9, 0x02]]),
-    (MapElementKind::DESERTLEDGE, [[0x2B0, 0x40]]),
-    (MapElementKind::FLOATINGISLAND, [[0x285, 0x40]]),
-    (MapElementKind::HOBO, [[0x3C9, 0x01]]),
-    (MapElementKind::KINGZORA, [[0x410, 0x02]]),
-    (MapElementKind::ZORALEDGE, [[0x301, 0x40]]),
-    (MapElementKind::LAKEHYLIAISLAND, [[0x2B5, 0x40]]),
-    (MapElementKind::PED, [[0x300, 0x40]]),
-    (MapElementKind::MUSHROOM, [[0x411, 0x10]]),
-    (MapElementKind::OLDMAN, [[0x410, 0x01]]),
-    (MapElementKind::SPECROCK, [[0x283, 0x40]]),
-    (MapElementKind::SUNKENTREASURE, [[0x2BB, 0x40]]),
-    (MapElementKind::BUMPERCAVELEDGE, [[0x2CA, 0x40]]),
-    (MapElementKind::MAZERACE, [[0x2A8, 0x40]]),
-    (MapElementKind::DIGGAME, [[0x2E8, 0x40]]),
-    (MapElementKind::STUMPY, [[0x410, 0x08]]),
-    (MapElementKind::FLUTESPOT, [[0x2AA, 0x40]]),
-    (MapElementKind::PURPLECHEST, [[0x3C9, 0x10]]),
-    (MapElementKind::PYRAMID, [[0x2DB, 0x40]]),
-    (MapElementKind::CATFISH, [[0x410, 0x20]]),
-]);
-
-
-
-func _connect_at():
-    # Initiate connection to the given URL.
-    if _at_status != AUTOTRACKER_STATUS.DISCONNECTED:
-        return
-
-    _client.set_verify_ssl_enabled(false)
-    var err = _client.connect_to_url(websocket_url)
-    if err != OK:
-        print("Unable to connect")
-        set_process(false)
-    else:
-
-
-func _closed(_was_clean = false):
-    _timer.stop()
-    remove_child(_timer)
-    _location_data = null
-    _old_location_data = null
-    _at_status = AUTOTRACKER_STATUS.DISCONNECTED
-    OS.set_window_title("Entrando - Auto-tracking disabled")
-
-func _connected(_proto = ""):
-    _client.get_peer(1).set_write_mode(WebSocketPeer.WRITE_MODE_TEXT)
-    var init_data = {'Opcode': "DeviceList", 'Space': "SNES"}
-    _client.get_peer(1).put_packet(JSON.print(init_data).to_utf8())
-    _at_status = AUTOTRACKER_STATUS.CONNECTING
-
-func _on_data():
-    var res_raw =_client.get_peer(1).get_packet()
-    if _at_status == AUTOTRACKER_STATUS.CONNECTING:
-        var res = JSON.parse(res_raw.get_string_from_utf8())
-        if res.error != OK:
-            print("Error parsing JSON")
-            return    
-        var device = res.result['Results'][0]
-        var connect_data = {'Opcode': "Attach", 'Space': "SNES", 'Operands': [device]}
-        _client.get_peer(1).put_packet(JSON.print(connect_data).to_utf8())
-        OS.set_window_title("Entrando - Auto-tracking enabled: connected to " + device)
-
-        _at_status = AUTOTRACKER_STATUS.CONNECTED
-        _timer.start()
-
-    elif _at_status == AUTOTRACKER_STATUS.CONNECTED:
-        if VALID_GAMEMODES.has(res_raw[0]):
-            _at_status = AUTOTRACKER_STATUS.TRACKING
-            _timer.paused = true
-            get_location_data()
-
-func get_location_data():
-     _client.disconnect("data_received", self, "_on_data")
-     _client.connect("data_received", self, "_build_location_data")
-     read_snes_mem(SAVEDATA_START, 0x400)
-     read_snes_mem(SAVEDATA_START + 0x410, 2)
-
-func process_location_data():
-    if _old_location_data == null:
-        _old_location_data = PoolByteArray()
-        _old_location_data.resize(_location_data.size())
-        _old_location_data.fill(0)
-
-    var underworld = get_parent().find_node("GUILayer")
-    var lightworld = get_parent().find_node("LightWorld")
-    var darkworld = get_parent().find_node("DarkWorld")
-
-    for loc in locations_to_sram:
-        var locs_data = locations_to_sram[loc]
-        var all_locs_checked = true            
-        var any_locs_checked  = false
-        var was_any_change = false
-        for loc_data in locs_data:
-            var addr = loc_data[0]
-            var mask = loc_data[1]
-            var new_value = _location_data[addr] & mask
-            var old_value = _old_location_data[addr] & mask
-            was_any_change = was_any_change || (new_value != old_value)
-            any_locs_checked = any_locs_checked || (new_value == mask)
-            all_locs_checked = all_locs_checked && (new_value == mask)
-        if was_any_change and (all_locs_checked or any_locs_checked):
-            var underworld_node = underworld.find_node(loc)
-            if (underworld_node):
-                underworld_node.get_child(0).set_pressed_texture(DISABLED_TEXTURE if all_locs_checked else TODO_TEXTURE);
-                underworld_node.get_child(0).set_pressed(true)
-            else:
-                var overworld_node = lightworld.find_node(loc)
-                if (overworld_node == null):
-                    overworld_node = darkworld.find_node(loc)
-                    if (overworld_node == null):
-                        print("Error Autotracking: Unable to find node " + loc)
-                        continue
-                overworld_node.get_child(0).hide()
-                # Do this to allow ctrl-z to undo
-                Util.add_hidden(overworld_node.get_child(0))
-
-    _old_location_data = _location_data
-    _location_data = null
-    _timer.paused = false
-    _at_status = AUTOTRACKER_STATUS.CONNECTED
-
-func _build_location_data():
-    var res_raw =_client.get_peer(1).get_packet()
-    if _location_data == null:
-        _location_data = res_raw
-        
-        # Add empty data to pad the array for the SRAM we aren't reading
-        # This allows us to address the array with the real SRAM addresses
-        var empty_data = PoolByteArray()
-        empty_data.resize(0x410 - res_raw.size())
-        empty_data.fill(0)
-        _location_data = _location_data + empty_data
-        return
-    else:
-        _location_data = _location_data + res_raw
-    if _location_data.size() == 0x410 + 2:
-        _client.disconnect("data_received", self, "_build_location_data")
-        _client.connect("data_received", self, "_on_data")
-        process_location_data()
-
-func read_snes_mem(addr, size):
-    var read_data = {'Opcode': "GetAddress", 'Space': "SNES", 'Operands': ["%x" % addr, "%x" % size]}
-    _client.get_peer(1).put_packet(JSON.print(read_data).to_utf8())
-
-func _on_Timer_timeout():
-    if _at_status == AUTOTRACKER_STATUS.CONNECTED:
-        read_snes_mem(WRAM_START + 0x10, 1)
-
-func _process(_delta):
-    _client.poll()
-
-pub fn start_autotracking() {
-    let client = ClientBuilder::new("ws://localhost:8080")
-        .unwrap()
-        .connect_insecure()
-        .unwrap();
+use std::sync::mpsc;
+use std::sync::Arc;
+use eframe::egui::Pos2;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc as tokio_mpsc;
+use tokio::time::MissedTickBehavior;
 
+use crate::game_state::{GameState, Transition, GAME_STATE_SIZE};
+use crate::memory_source::{MemorySource, SourceError};
+use crate::sram::{self, LevelChange, StatusChange};
 
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Reconnect delays double from the first value up to the second after each failed attempt.
+pub const RECONNECT_MIN: Duration = Duration::from_secs(1);
+pub const RECONNECT_MAX: Duration = Duration::from_secs(30);
+
+/// Offsets into WRAM.
+pub const SAVEDATA_OFFSET: u32 = 0xF000;
+/// Room data up to 0x400 plus the two NPC flag bytes at 0x410.
+pub const SAVEDATA_SIZE: usize = 0x410 + 2;
+pub const GAMEMODE_OFFSET: u32 = 0x10;
+
+/// Dungeon, overworld and special overworld. Save data read in any other mode can be stale.
+const VALID_GAMEMODES: [u8; 3] = [0x07, 0x09, 0x0B];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutotrackerStatus {
+    Disconnected,
+    /// Lost the connection and waiting to try again.
+    Reconnecting,
+    Connecting,
+    /// Attached to a device, but the game is not in a mode where save data can be trusted.
+    Connected,
+    Tracking
+}
+
+/// Everything the autotracker reports back to the UI thread.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutotrackerEvent {
+    Status(AutotrackerStatus),
+    /// A DeviceList answer came in.
+    Devices(Vec<String>),
+    Connected(String),
+    /// The connection dropped, the next attempt happens at the given time.
+    Reconnecting(Instant),
+    /// The game mode was read, whether or not save data followed.
+    Polled(Instant),
+    /// Save data that differs from the previous snapshot.
+    Snapshot(Vec<u8>),
+    /// Locations whose status differs from the previous snapshot.
+    ChecksChanged(Vec<StatusChange>),
+    /// Inventory items whose upgrade level differs from the previous snapshot.
+    InventoryChanged(Vec<LevelChange>),
+    /// Link went through an overworld entrance.
+    Transition(Transition),
+    /// Link's map-space position, None while he is not on either map.
+    LinkMoved(Option<Pos2>),
+    Error(String)
+}
+
+/// What the status panel shows about the connection, kept up to date from events.
+#[derive(Debug, Clone)]
+pub struct AutotrackerInfo {
+    pub status: AutotrackerStatus,
+    /// The attached device, if any.
+    pub device: Option<String>,
+    /// Everything the last DeviceList returned.
+    pub devices: Vec<String>,
+    pub last_poll: Option<Instant>,
+    /// When the next reconnect attempt happens while RECONNECTING.
+    pub retry_at: Option<Instant>,
+    pub error: Option<String>,
+    pub link_pos: Option<Pos2>
+}
+
+impl AutotrackerInfo {
+    fn new() -> AutotrackerInfo {
+        AutotrackerInfo {
+            status: AutotrackerStatus::Connecting,
+            device: None,
+            devices: Vec::new(),
+            last_poll: None,
+            retry_at: None,
+            error: None,
+            link_pos: None
+        }
+    }
+
+    fn apply(&mut self, event: &AutotrackerEvent) {
+        match event {
+            AutotrackerEvent::Status(status) => {
+                self.status = *status;
+                if *status == AutotrackerStatus::Connecting {
+                    self.retry_at = None;
+                }
+            }
+            AutotrackerEvent::Devices(devices) => self.devices = devices.clone(),
+            AutotrackerEvent::Connected(device) => {
+                self.status = AutotrackerStatus::Connected;
+                self.device = Some(device.clone());
+                self.error = None;
+            }
+            AutotrackerEvent::Reconnecting(retry_at) => {
+                self.status = AutotrackerStatus::Reconnecting;
+                self.device = None;
+                self.devices.clear();
+                self.retry_at = Some(*retry_at);
+                self.link_pos = None;
+            }
+            AutotrackerEvent::LinkMoved(pos) => self.link_pos = *pos,
+            AutotrackerEvent::Polled(time) => self.last_poll = Some(*time),
+            AutotrackerEvent::Error(error) => self.error = Some(error.clone()),
+            AutotrackerEvent::Snapshot(_) | AutotrackerEvent::ChecksChanged(_) | AutotrackerEvent::InventoryChanged(_)
+                | AutotrackerEvent::Transition(_) => ()
+        }
+    }
+}
+
+/// Sends events to the UI thread and wakes it up.
+#[derive(Clone)]
+struct EventSender {
+    sender: mpsc::Sender<AutotrackerEvent>,
+    wake: Arc<dyn Fn() + Send + Sync>
+}
+
+impl EventSender {
+    fn send(&self, event: AutotrackerEvent) {
+        // The receiver only goes away together with the runtime, nothing left to tell then
+        if self.sender.send(event).is_ok() {
+            (self.wake)();
+        }
+    }
+
+    /// Queues an event without waking the UI thread, for events that change nothing on screen
+    /// until the UI repaints for another reason.
+    fn send_quietly(&self, event: AutotrackerEvent) {
+        let _ = self.sender.send(event);
+    }
+}
+
+/// Requests from the UI thread.
+enum Command {
+    Attach(String),
+    RefreshDevices
+}
+
+/// Polls a memory source and turns what it reads into events. Runs until the runtime shuts down.
+struct Tracker {
+    source: Box<dyn MemorySource>,
+    events: EventSender,
+    commands: tokio_mpsc::UnboundedReceiver<Command>,
+    poll_interval: Duration,
+    /// Attached to automatically when it shows up in the device list.
+    preferred_device: Option<String>,
+    status: AutotrackerStatus,
+    /// The last snapshot sent, check changes are relative to it.
+    last_snapshot: Option<Vec<u8>>,
+    last_game_state: Option<GameState>,
+    backoff: Duration
+}
+
+impl Tracker {
+    async fn run(mut self) {
+        loop {
+            let error = match self.connect().await {
+                Ok(()) => self.track().await,
+                Err(err) => err
+            };
+            self.source.disconnect();
+            self.last_game_state = None;
+
+            let delay = self.backoff;
+            self.backoff = (self.backoff * 2).min(RECONNECT_MAX);
+            self.status = AutotrackerStatus::Reconnecting;
+            self.events.send(AutotrackerEvent::Error(error.to_string()));
+            self.events.send(AutotrackerEvent::Reconnecting(Instant::now() + delay));
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn set_status(&mut self, status: AutotrackerStatus) {
+        if self.status != status {
+            self.status = status;
+            self.events.send(AutotrackerEvent::Status(status));
+        }
+    }
+
+    fn poll_timer(&self) -> tokio::time::Interval {
+        let mut interval = tokio::time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    }
+
+    /// Connects and attaches to a device, waiting for the user to pick one if there are several.
+    async fn connect(&mut self) -> Result<(), SourceError> {
+        self.set_status(AutotrackerStatus::Connecting);
+        self.source.connect().await?;
+
+        let mut devices: Vec<String> = Vec::new();
+        let mut interval = self.poll_timer();
+        loop {
+            let refresh = tokio::select! {
+                // Asked again on every poll, the console may just not be powered on yet
+                _ = interval.tick() => devices.is_empty(),
+                Some(command) = self.commands.recv() => match command {
+                    Command::Attach(device) => return self.attach(device).await,
+                    Command::RefreshDevices => true
+                }
+            };
+            if !refresh {
+                continue;
+            }
+
+            devices = self.source.devices().await?;
+            self.events.send(AutotrackerEvent::Devices(devices.clone()));
+
+            // With several devices the user picks one, unless the last one used is among them
+            let preferred = self.preferred_device.clone().filter(|device| devices.contains(device));
+            match preferred {
+                Some(device) => return self.attach(device).await,
+                None if devices.len() == 1 => return self.attach(devices[0].clone()).await,
+                None if devices.is_empty() => self.events.send(AutotrackerEvent::Error(String::from("No devices found"))),
+                None => self.events.send(AutotrackerEvent::Error(String::from("Several devices found, pick one")))
+            }
+        }
+    }
+
+    async fn attach(&mut self, device: String) -> Result<(), SourceError> {
+        self.source.attach(&device).await?;
+        // Reconnects go straight back to the same device
+        self.preferred_device = Some(device.clone());
+        self.backoff = RECONNECT_MIN;
+        self.status = AutotrackerStatus::Connected;
+        self.events.send(AutotrackerEvent::Connected(device));
+        Ok(())
+    }
+
+    /// Polls until something goes wrong.
+    async fn track(&mut self) -> SourceError {
+        let mut interval = self.poll_timer();
+        loop {
+            let result = tokio::select! {
+                _ = interval.tick() => self.poll().await,
+                Some(command) = self.commands.recv() => match command {
+                    Command::Attach(device) => self.attach(device).await,
+                    Command::RefreshDevices => Ok(())
+                }
+            };
+            if let Err(err) = result {
+                return err;
+            }
+        }
+    }
+
+    async fn in_valid_gamemode(&mut self) -> Result<bool, SourceError> {
+        // Without game state there is only saved data, which is always settled
+        if !self.source.has_game_state() {
+            return Ok(true);
+        }
+
+        let gamemode = self.source.read_wram(GAMEMODE_OFFSET, 1).await?;
+        let gamemode = gamemode.first().copied().ok_or_else(|| SourceError::Protocol(String::from("empty read")))?;
+        Ok(VALID_GAMEMODES.contains(&gamemode))
+    }
+
+    async fn poll(&mut self) -> Result<(), SourceError> {
+        let valid = self.in_valid_gamemode().await?;
+        self.events.send_quietly(AutotrackerEvent::Polled(Instant::now()));
+        if !valid {
+            // Title screen, file select, resets and save-and-quit all land here
+            self.set_status(AutotrackerStatus::Connected);
+            return Ok(());
+        }
+
+        self.set_status(AutotrackerStatus::Tracking);
+        let savedata = self.source.read_wram(SAVEDATA_OFFSET, SAVEDATA_SIZE).await?;
+
+        // Read the game mode again in case the game reset while the save data was read
+        if !self.in_valid_gamemode().await? {
+            self.set_status(AutotrackerStatus::Connected);
+            return Ok(());
+        }
+
+        // Memory reads as all zeros for a moment while the console resets
+        if savedata.iter().any(|byte| *byte != 0) {
+            self.publish_snapshot(savedata);
+        }
+
+        if self.source.has_game_state() {
+            let data = self.source.read_wram(0, GAME_STATE_SIZE).await?;
+            if let Some(state) = GameState::parse(&data) {
+                self.follow_link(state);
+            }
+        }
+        Ok(())
+    }
+
+    fn follow_link(&mut self, state: GameState) {
+        let last = self.last_game_state.replace(state);
+        if last.map(|last| last.map_pos()) != Some(state.map_pos()) {
+            self.events.send(AutotrackerEvent::LinkMoved(state.map_pos()));
+        }
+
+        let Some(last) = last else { return; };
+        if last.indoors || !state.indoors {
+            return;
+        }
+
+        // Polls are a while apart, so where Link was last seen outside is close to, not at, the door
+        if let Some(from) = last.map_pos() {
+            self.events.send(AutotrackerEvent::Transition(Transition { from, room: state.room, entrance: state.entrance }));
+        }
+    }
+
+    fn publish_snapshot(&mut self, savedata: Vec<u8>) {
+        let old = self.last_snapshot.take().unwrap_or_else(|| vec![0; savedata.len()]);
+        if old != savedata {
+            let changes = sram::diff(&old, &savedata);
+            let inventory = sram::diff_inventory(&old, &savedata);
+            self.events.send(AutotrackerEvent::Snapshot(savedata.clone()));
+            if !changes.is_empty() {
+                self.events.send(AutotrackerEvent::ChecksChanged(changes));
+            }
+            if !inventory.is_empty() {
+                self.events.send(AutotrackerEvent::InventoryChanged(inventory));
+            }
+        }
+        self.last_snapshot = Some(savedata);
+    }
 }
 
+/// Tracks checks from a memory source on its own runtime.
+/// Everything it learns arrives as events, drained by the UI with `poll_events`.
+pub struct Autotracker {
+    events: mpsc::Receiver<AutotrackerEvent>,
+    info: AutotrackerInfo,
+    commands: tokio_mpsc::UnboundedSender<Command>,
+    runtime: Option<tokio::runtime::Runtime>
+}
+
+impl Autotracker {
+    /// `wake` is called after every event that changes what the UI shows, typically to request a repaint.
+    pub fn start(
+        source: Box<dyn MemorySource>,
+        poll_interval: Duration,
+        preferred_device: Option<String>,
+        wake: impl Fn() + Send + Sync + 'static
+    ) -> Result<Autotracker, String> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|err| format!("Unable to start autotracker: {}", err))?;
+
+        let (sender, receiver) = mpsc::channel();
+        let (command_sender, command_receiver) = tokio_mpsc::unbounded_channel();
+        let tracker = Tracker {
+            source,
+            events: EventSender { sender, wake: Arc::new(wake) },
+            commands: command_receiver,
+            poll_interval,
+            preferred_device,
+            status: AutotrackerStatus::Disconnected,
+            last_snapshot: None,
+            last_game_state: None,
+            backoff: RECONNECT_MIN
+        };
+        runtime.spawn(tracker.run());
 
-*/
\ No newline at end of file
+        Ok(Autotracker {
+            events: receiver,
+            info: AutotrackerInfo::new(),
+            commands: command_sender,
+            runtime: Some(runtime)
+        })
+    }
+
+    /// Everything that happened since the last call, oldest first. Also updates `info`.
+    pub fn poll_events(&mut self) -> Vec<AutotrackerEvent> {
+        let events: Vec<AutotrackerEvent> = self.events.try_iter().collect();
+        for event in &events {
+            self.info.apply(event);
+        }
+
+        events
+    }
+
+    pub fn info(&self) -> &AutotrackerInfo {
+        &self.info
+    }
+
+    pub fn attach(&self, device: String) {
+        let _ = self.commands.send(Command::Attach(device));
+    }
+
+    pub fn refresh_devices(&self) {
+        let _ = self.commands.send(Command::RefreshDevices);
+    }
+}
+
+impl Drop for Autotracker {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Waiting helpers for the tests of the autotracker and of every memory source.
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use super::*;
+    use crate::sram::StatusChange;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// `size` zero bytes with the given ones set, for save data and WRAM fixtures.
+    pub(crate) fn buffer(size: usize, bytes: &[(usize, u8)]) -> Vec<u8> {
+        let mut data = vec![0; size];
+        for (offset, value) in bytes {
+            data[*offset] = *value;
+        }
+        data
+    }
+
+    pub(crate) fn wait_until(mut condition: impl FnMut() -> bool) {
+        let deadline = Instant::now() + TIMEOUT;
+        while !condition() {
+            assert!(Instant::now() < deadline, "Timed out waiting for the autotracker");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Drains events until one matches, returning it.
+    pub(crate) fn wait_for_event(tracker: &mut Autotracker, matches: impl Fn(&AutotrackerEvent) -> bool) -> AutotrackerEvent {
+        let mut found = None;
+        wait_until(|| {
+            found = tracker.poll_events().into_iter().find(|event| matches(event));
+            found.is_some()
+        });
+        found.unwrap()
+    }
+
+    pub(crate) fn wait_for_checks(tracker: &mut Autotracker) -> Vec<StatusChange> {
+        match wait_for_event(tracker, |event| matches!(event, AutotrackerEvent::ChecksChanged(_))) {
+            AutotrackerEvent::ChecksChanged(changes) => changes,
+            _ => unreachable!()
+        }
+    }
+
+    pub(crate) fn wait_for_status(tracker: &mut Autotracker, status: AutotrackerStatus) {
+        wait_until(|| {
+            tracker.poll_events();
+            tracker.info().status == status
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_helpers::*;
+    use crate::map_element::MapElementKind;
+    use crate::mock_usb2snes::{MockUsb2Snes, Snapshot, DUNGEON_GAMEMODE, OVERWORLD_GAMEMODE};
+    use crate::sram::CheckStatus;
+    use crate::usb2snes::Usb2SnesSource;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const TEST_POLL_INTERVAL: Duration = Duration::from_millis(20);
+    const TITLE_SCREEN_GAMEMODE: u8 = 0x01;
+
+    fn source(server: &MockUsb2Snes) -> Box<dyn MemorySource> {
+        Box::new(Usb2SnesSource::new(server.url()).expect("Mock URL should parse"))
+    }
+
+    fn start(server: &MockUsb2Snes) -> Autotracker {
+        Autotracker::start(source(server), TEST_POLL_INTERVAL, None, || ()).expect("Autotracker should start")
+    }
+
+    fn wait_for_snapshot(tracker: &mut Autotracker) -> Vec<u8> {
+        match wait_for_event(tracker, |event| matches!(event, AutotrackerEvent::Snapshot(_))) {
+            AutotrackerEvent::Snapshot(snapshot) => snapshot,
+            _ => unreachable!()
+        }
+    }
+
+    /// Waits for a few full polls, long enough for any snapshot to have come through.
+    fn wait_for_polls(server: &MockUsb2Snes, polls: usize) {
+        let target = server.gamemode_reads() + polls;
+        wait_until(|| server.gamemode_reads() >= target);
+    }
+
+    fn has_snapshot(events: &[AutotrackerEvent]) -> bool {
+        events.iter().any(|event| matches!(event, AutotrackerEvent::Snapshot(_)))
+    }
+
+    #[test]
+    fn attaches_to_single_device_and_reads_save_data() {
+        let savedata = buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)]);
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], vec![Snapshot::new(OVERWORLD_GAMEMODE, savedata.clone())]);
+        let mut tracker = start(&server);
+
+        assert_eq!(wait_for_snapshot(&mut tracker), savedata);
+        assert_eq!(server.attached().as_deref(), Some("SD2SNES COM3"));
+
+        let info = tracker.info();
+        assert_eq!(info.status, AutotrackerStatus::Tracking);
+        assert_eq!(info.device.as_deref(), Some("SD2SNES COM3"));
+        assert!(info.last_poll.is_some());
+    }
+
+    #[test]
+    fn waits_for_a_pick_between_several_devices() {
+        let server = MockUsb2Snes::start(&["SD2SNES COM3", "EmuNWA snes9x"], vec![Snapshot::blank(OVERWORLD_GAMEMODE)]);
+        let mut tracker = start(&server);
+
+        wait_for_event(&mut tracker, |event| matches!(event, AutotrackerEvent::Devices(devices) if devices.len() == 2));
+        assert_eq!(tracker.info().status, AutotrackerStatus::Connecting);
+        assert_eq!(server.attached(), None);
+
+        tracker.attach(String::from("EmuNWA snes9x"));
+        let connected = wait_for_event(&mut tracker, |event| matches!(event, AutotrackerEvent::Connected(_)));
+        assert_eq!(connected, AutotrackerEvent::Connected(String::from("EmuNWA snes9x")));
+        wait_until(|| server.attached().is_some());
+        assert_eq!(server.attached().as_deref(), Some("EmuNWA snes9x"));
+    }
+
+    #[test]
+    fn ignores_reads_outside_valid_game_modes() {
+        let script = vec![
+            Snapshot::new(TITLE_SCREEN_GAMEMODE, buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)])),
+            Snapshot::new(OVERWORLD_GAMEMODE, buffer(SAVEDATA_SIZE, &[(0x216, 0x10)]))
+        ];
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], script);
+        let mut tracker = start(&server);
+
+        wait_for_polls(&server, 3);
+        assert!(!has_snapshot(&tracker.poll_events()));
+        assert_eq!(tracker.info().status, AutotrackerStatus::Connected);
+
+        assert!(server.advance());
+        assert_eq!(wait_for_snapshot(&mut tracker), buffer(SAVEDATA_SIZE, &[(0x216, 0x10)]));
+    }
+
+    #[test]
+    fn ignores_all_zero_save_data() {
+        let script = vec![Snapshot::blank(OVERWORLD_GAMEMODE), Snapshot::new(OVERWORLD_GAMEMODE, buffer(SAVEDATA_SIZE, &[(0x410, 0x80)]))];
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], script);
+        let mut tracker = start(&server);
+
+        wait_for_polls(&server, 4);
+        assert!(!has_snapshot(&tracker.poll_events()));
+
+        assert!(server.advance());
+        assert_eq!(wait_for_snapshot(&mut tracker), buffer(SAVEDATA_SIZE, &[(0x410, 0x80)]));
+    }
+
+    #[test]
+    fn only_reports_changes() {
+        let script = vec![
+            Snapshot::new(OVERWORLD_GAMEMODE, buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)])),
+            Snapshot::new(OVERWORLD_GAMEMODE, buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)])),
+            Snapshot::new(OVERWORLD_GAMEMODE, buffer(SAVEDATA_SIZE, &[(0x216, 0x10)]))
+        ];
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], script);
+        let mut tracker = start(&server);
+        wait_for_snapshot(&mut tracker);
+
+        assert!(server.advance());
+        wait_for_polls(&server, 4);
+        assert!(!has_snapshot(&tracker.poll_events()));
+
+        assert!(server.advance());
+        let changes = wait_for_checks(&mut tracker);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.contains(&StatusChange { kind: MapElementKind::HOOKSHOT, old: CheckStatus::Complete, new: CheckStatus::None }));
+        assert!(changes.contains(&StatusChange { kind: MapElementKind::DAM, old: CheckStatus::None, new: CheckStatus::Complete }));
+    }
+
+    #[test]
+    fn reports_inventory_upgrades() {
+        let mut sword = buffer(SAVEDATA_SIZE, &[(0x359, 1)]);
+        sword[0x354] = 1;
+        let mut tempered = sword.clone();
+        tempered[0x359] = 3;
+        tempered[0x354] = 2;
+        let script = vec![Snapshot::new(OVERWORLD_GAMEMODE, sword), Snapshot::new(OVERWORLD_GAMEMODE, tempered)];
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], script);
+        let mut tracker = start(&server);
+
+        let inventory = |event: AutotrackerEvent| match event {
+            AutotrackerEvent::InventoryChanged(changes) => changes,
+            _ => unreachable!()
+        };
+        let changes = inventory(wait_for_event(&mut tracker, |event| matches!(event, AutotrackerEvent::InventoryChanged(_))));
+        assert_eq!(changes, vec![
+            LevelChange { kind: MapElementKind::GLOVE, old: 0, new: 1 },
+            LevelChange { kind: MapElementKind::SWORD, old: 0, new: 1 }
+        ]);
+
+        assert!(server.advance());
+        let changes = inventory(wait_for_event(&mut tracker, |event| matches!(event, AutotrackerEvent::InventoryChanged(_))));
+        assert_eq!(changes, vec![
+            LevelChange { kind: MapElementKind::GLOVE, old: 1, new: 2 },
+            LevelChange { kind: MapElementKind::SWORD, old: 1, new: 3 }
+        ]);
+    }
+
+    #[test]
+    fn follows_link_on_both_maps() {
+        let savedata = buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)]);
+        let script = vec![
+            Snapshot::new(OVERWORLD_GAMEMODE, savedata.clone()).outside(0x1E, 2048, 1024),
+            Snapshot::new(OVERWORLD_GAMEMODE, savedata).outside(0x5E, 2048, 1024)
+        ];
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], script);
+        let mut tracker = start(&server);
+
+        wait_for_event(&mut tracker, |event| matches!(event, AutotrackerEvent::LinkMoved(Some(_))));
+        assert_eq!(tracker.info().link_pos, Some(Pos2::new(375.0, 187.5)));
+
+        assert!(server.advance());
+        wait_for_event(&mut tracker, |event| matches!(event, AutotrackerEvent::LinkMoved(Some(_))));
+        assert_eq!(tracker.info().link_pos, Some(Pos2::new(1125.0, 187.5)));
+    }
+
+    #[test]
+    fn reports_entering_the_underworld() {
+        let savedata = buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)]);
+        let script = vec![
+            Snapshot::new(OVERWORLD_GAMEMODE, savedata.clone()).outside(0x1E, 2232, 2823),
+            Snapshot::new(DUNGEON_GAMEMODE, savedata).inside(0xC9, 0x08)
+        ];
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], script);
+        let mut tracker = start(&server);
+        wait_for_snapshot(&mut tracker);
+        wait_for_polls(&server, 4);
+
+        assert!(server.advance());
+        let transition = match wait_for_event(&mut tracker, |event| matches!(event, AutotrackerEvent::Transition(_))) {
+            AutotrackerEvent::Transition(transition) => transition,
+            _ => unreachable!()
+        };
+        assert_eq!(tracker.info().link_pos, None);
+        assert_eq!(transition.room, 0xC9);
+        assert_eq!(transition.entrance, 0x08);
+        assert!((transition.from.x - 408.7).abs() < 0.5);
+        assert!((transition.from.y - 516.9).abs() < 0.5);
+    }
+
+    #[test]
+    fn does_not_wake_the_ui_for_unchanged_polls() {
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], vec![Snapshot::new(OVERWORLD_GAMEMODE, buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)]))]);
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let counter = wakes.clone();
+        let mut tracker = Autotracker::start(source(&server), TEST_POLL_INTERVAL, None, move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }).expect("Autotracker should start");
+
+        wait_for_snapshot(&mut tracker);
+        wait_for_polls(&server, 2);
+        let woken = wakes.load(Ordering::Relaxed);
+        assert!(woken > 0);
+
+        wait_for_polls(&server, 3);
+        assert_eq!(wakes.load(Ordering::Relaxed), woken);
+    }
+
+    #[test]
+    fn reconnects_after_losing_the_connection() {
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], vec![Snapshot::new(OVERWORLD_GAMEMODE, buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)]))]);
+        let mut tracker = start(&server);
+        wait_for_snapshot(&mut tracker);
+
+        server.disconnect_all();
+        wait_for_status(&mut tracker, AutotrackerStatus::Reconnecting);
+        assert!(tracker.info().retry_at.is_some());
+
+        wait_for_status(&mut tracker, AutotrackerStatus::Tracking);
+        assert_eq!(tracker.info().device.as_deref(), Some("SD2SNES COM3"));
+    }
+
+    #[test]
+    fn replays_snapshot_files() {
+        // Hand-made save data, not a dump from a console: Hookshot Cave, the Dam and the Library
+        // collected and two of the Paradox Cave chests open
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/synthetic_hookshot_dam_library.bin");
+        let snapshot = Snapshot::from_file(&path).expect("Snapshot file should load");
+        let server = MockUsb2Snes::start(&["SD2SNES COM3"], vec![snapshot]);
+        let mut tracker = start(&server);
+
+        let changes = wait_for_checks(&mut tracker);
+        let completed: Vec<MapElementKind> = changes.iter()
+            .filter(|change| change.new == CheckStatus::Complete)
+            .map(|change| change.kind)
+            .collect();
+        assert_eq!(completed.len(), 3);
+        assert!(completed.contains(&MapElementKind::HOOKSHOT));
+        assert!(completed.contains(&MapElementKind::DAM));
+        assert!(completed.contains(&MapElementKind::LIBRARY));
+        assert!(changes.iter().any(|change| change.kind == MapElementKind::PARAM && change.new == CheckStatus::Partial));
+    }
+}
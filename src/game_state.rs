@@ -0,0 +1,201 @@
+use eframe::egui::Pos2;
+
+use crate::map_element;
+use map_element::MapElementKind;
+
+use crate::layout::MAP_SIZE;
+
+/// Low WRAM read on every poll to follow Link around, from offset 0.
+pub const GAME_STATE_SIZE: usize = 0x110;
+
+const INDOORS_OFFSET: usize = 0x1B;
+const LINK_Y_OFFSET: usize = 0x20;
+const LINK_X_OFFSET: usize = 0x22;
+const OVERWORLD_SCREEN_OFFSET: usize = 0x8A;
+const ROOM_OFFSET: usize = 0xA0;
+const ENTRANCE_OFFSET: usize = 0x10E;
+
+/// Width and height of one world in overworld pixel coordinates.
+const WORLD_SIZE: f32 = 4096.0;
+/// Overworld screens from here on are the dark world.
+const DARK_WORLD_SCREEN: u8 = 0x40;
+/// Screens from here on are special areas like the pedestal, which are not on the maps.
+const SPECIAL_SCREEN: u8 = 0x80;
+
+/// Entrance ids ($010E) of the doors of dungeons and caves with several ends. The randomizer
+/// rewrites which entrance a door loads, so the id names what lies behind the door Link used.
+pub(crate) const ENTRANCE_DESTINATIONS: &[(u8, MapElementKind)] = &[
+    (0x03, MapElementKind::HCL),
+    (0x04, MapElementKind::HCD),
+    (0x05, MapElementKind::HCR),
+    (0x06, MapElementKind::DARK),
+    (0x07, MapElementKind::RESCUE),
+    (0x08, MapElementKind::EP),
+    (0x09, MapElementKind::DPD),
+    (0x0A, MapElementKind::DPR),
+    (0x0B, MapElementKind::DPL),
+    (0x0C, MapElementKind::DPU),
+    (0x0D, MapElementKind::ELDER),
+    (0x0E, MapElementKind::ELDER),
+    (0x0F, MapElementKind::BROTHERS),
+    (0x10, MapElementKind::BROTHERS),
+    (0x13, MapElementKind::SUPERBUNNYL),
+    (0x14, MapElementKind::SUPERBUNNYU),
+    (0x15, MapElementKind::TRL),
+    (0x16, MapElementKind::BUMPERL),
+    (0x17, MapElementKind::BUMPERU),
+    (0x18, MapElementKind::TRU),
+    (0x19, MapElementKind::TRR),
+    (0x1C, MapElementKind::SPIRALL),
+    (0x1D, MapElementKind::SPIRALU),
+    (0x1E, MapElementKind::PARAL),
+    (0x1F, MapElementKind::PARAM),
+    (0x20, MapElementKind::PARAU),
+    (0x21, MapElementKind::SPECL),
+    (0x22, MapElementKind::SPECM),
+    (0x23, MapElementKind::SPECU),
+    (0x24, MapElementKind::AT),
+    (0x25, MapElementKind::SP),
+    (0x26, MapElementKind::PD),
+    (0x27, MapElementKind::MM),
+    (0x28, MapElementKind::SW),
+    (0x29, MapElementKind::SW),
+    (0x2A, MapElementKind::SW),
+    (0x2B, MapElementKind::SW),
+    (0x2D, MapElementKind::IP),
+    (0x2E, MapElementKind::DESCENT),
+    (0x2F, MapElementKind::DESCENT),
+    (0x30, MapElementKind::OLDMANL),
+    (0x31, MapElementKind::OLDMANU),
+    (0x33, MapElementKind::TH),
+    (0x34, MapElementKind::TT),
+    (0x35, MapElementKind::TRD),
+    (0x37, MapElementKind::GT),
+    (0x3A, MapElementKind::HOOKSHOT),
+    (0x3B, MapElementKind::HOOKSHOT)
+];
+
+/// Underworld rooms right behind the door of single entrance caves, for when the entrance id
+/// is not one of the above.
+const ROOM_DESTINATIONS: &[(u16, MapElementKind)] = &[
+    (0x3C, MapElementKind::HOOKSHOT),
+    (0x10C, MapElementKind::MIMIC),
+    (0x114, MapElementKind::WATERFALL),
+    (0x116, MapElementKind::PFAIRY),
+    (0x117, MapElementKind::SPIKE),
+    (0x11E, MapElementKind::HYPE),
+    (0x120, MapElementKind::ICEROD),
+    (0x123, MapElementKind::MMC)
+];
+
+/// Where Link is, as far as the tracker cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameState {
+    pub indoors: bool,
+    pub screen: u8,
+    pub x: u16,
+    pub y: u16,
+    pub room: u16,
+    /// The entrance last used to go inside.
+    pub entrance: u8
+}
+
+/// Link went from the overworld into the underworld.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    /// Map-space position Link was last seen at outside.
+    pub from: Pos2,
+    pub room: u16,
+    pub entrance: u8
+}
+
+fn word(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+impl GameState {
+    /// `data` is the first `GAME_STATE_SIZE` bytes of WRAM.
+    pub fn parse(data: &[u8]) -> Option<GameState> {
+        if data.len() < GAME_STATE_SIZE {
+            return None;
+        }
+
+        Some(GameState {
+            indoors: data[INDOORS_OFFSET] != 0,
+            screen: data[OVERWORLD_SCREEN_OFFSET],
+            x: word(data, LINK_X_OFFSET),
+            y: word(data, LINK_Y_OFFSET),
+            room: word(data, ROOM_OFFSET),
+            entrance: data[ENTRANCE_OFFSET]
+        })
+    }
+
+    /// Link's map-space position while on one of the two maps.
+    pub fn map_pos(&self) -> Option<Pos2> {
+        if self.indoors || self.screen >= SPECIAL_SCREEN {
+            return None;
+        }
+
+        let x_offset = if self.screen >= DARK_WORLD_SCREEN { MAP_SIZE } else { 0.0 };
+        Some(Pos2::new(
+            self.x as f32 / WORLD_SIZE * MAP_SIZE + x_offset,
+            self.y as f32 / WORLD_SIZE * MAP_SIZE
+        ))
+    }
+}
+
+impl Transition {
+    /// The icon for what lies behind the door Link used, if it is a known destination.
+    pub fn destination(&self) -> Option<MapElementKind> {
+        entrance_destination(self.entrance).or_else(|| room_destination(self.room))
+    }
+}
+
+pub fn entrance_destination(entrance: u8) -> Option<MapElementKind> {
+    ENTRANCE_DESTINATIONS.iter().find(|(known, _)| *known == entrance).map(|(_, kind)| *kind)
+}
+
+pub fn room_destination(room: u16) -> Option<MapElementKind> {
+    ROOM_DESTINATIONS.iter().find(|(known, _)| *known == room).map(|(_, kind)| *kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autotracker::test_helpers::buffer;
+
+
+    #[test]
+    fn parses_link_from_low_wram() {
+        let data = buffer(GAME_STATE_SIZE, &[(0x1B, 1), (0x20, 0x34), (0x21, 0x12), (0x22, 0x78), (0x23, 0x06), (0x8A, 0x45), (0xA0, 0x17), (0xA1, 0x01), (0x10E, 0x21)]);
+        assert_eq!(GameState::parse(&data), Some(GameState { indoors: true, screen: 0x45, x: 0x0678, y: 0x1234, room: 0x117, entrance: 0x21 }));
+        assert_eq!(GameState::parse(&data[..GAME_STATE_SIZE - 1]), None);
+    }
+
+    #[test]
+    fn places_link_on_the_right_map() {
+        let light = GameState { indoors: false, screen: 0x1E, x: 2048, y: 1024, room: 0, entrance: 0 };
+        assert_eq!(light.map_pos(), Some(Pos2::new(MAP_SIZE / 2.0, MAP_SIZE / 4.0)));
+
+        // The dark world is drawn right of the light world
+        let dark = GameState { screen: 0x5E, ..light };
+        assert_eq!(dark.map_pos(), Some(Pos2::new(MAP_SIZE + MAP_SIZE / 2.0, MAP_SIZE / 4.0)));
+
+        assert_eq!(GameState { indoors: true, ..light }.map_pos(), None);
+        assert_eq!(GameState { screen: 0x80, ..light }.map_pos(), None);
+    }
+
+    #[test]
+    fn finds_destinations_by_entrance_then_room() {
+        assert_eq!(entrance_destination(0x15), Some(MapElementKind::TRL));
+        assert_eq!(entrance_destination(0x1F), Some(MapElementKind::PARAM));
+        assert_eq!(entrance_destination(0x00), None);
+
+        assert_eq!(room_destination(0x117), Some(MapElementKind::SPIKE));
+        assert_eq!(room_destination(0x00), None);
+
+        let transition = Transition { from: Pos2::ZERO, room: 0x117, entrance: 0x22 };
+        assert_eq!(transition.destination(), Some(MapElementKind::SPECM));
+        assert_eq!(Transition { entrance: 0x5A, ..transition }.destination(), Some(MapElementKind::SPIKE));
+    }
+}
@@ -0,0 +1,213 @@
+use uuid::Uuid;
+use std::collections::HashMap;
+
+use crate::map_element;
+use map_element::*;
+
+use crate::row_element;
+use row_element::*;
+
+use crate::layout::ROW_MAX_LENGTH;
+
+/// A single reversible change to the tracker state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    AddPlacedIcon(MapElement),
+    RemovePlacedIcon(MapElement),
+    MovePlacedIcon { from: MapElement, to: MapElement },
+    AddEntrance(MapElement),
+    RemoveEntrance(MapElement),
+    UpdateEntrance { from: MapElement, to: MapElement },
+    SetRowElement { row: usize, column: usize, from: RowElement, to: RowElement }
+}
+
+impl Edit {
+    pub fn inverse(&self) -> Edit {
+        match self {
+            Edit::AddPlacedIcon(item) => Edit::RemovePlacedIcon(item.clone()),
+            Edit::RemovePlacedIcon(item) => Edit::AddPlacedIcon(item.clone()),
+            Edit::MovePlacedIcon { from, to } => Edit::MovePlacedIcon { from: to.clone(), to: from.clone() },
+            Edit::AddEntrance(item) => Edit::RemoveEntrance(item.clone()),
+            Edit::RemoveEntrance(item) => Edit::AddEntrance(item.clone()),
+            Edit::UpdateEntrance { from, to } => Edit::UpdateEntrance { from: to.clone(), to: from.clone() },
+            Edit::SetRowElement { row, column, from, to } => Edit::SetRowElement {
+                row: *row,
+                column: *column,
+                from: to.clone(),
+                to: from.clone()
+            }
+        }
+    }
+
+    pub fn apply(
+        &self,
+        entrances: &mut HashMap<Uuid, MapElement>,
+        placed_icons: &mut HashMap<Uuid, MapElement>,
+        row_icons: &mut [[RowElement; ROW_MAX_LENGTH]; 4]
+    ) {
+        match self {
+            Edit::AddPlacedIcon(item) => {
+                placed_icons.insert(item.id, item.clone());
+            }
+            Edit::RemovePlacedIcon(item) => {
+                placed_icons.remove(&item.id);
+            }
+            Edit::MovePlacedIcon { from, to } => {
+                placed_icons.remove(&from.id);
+                placed_icons.insert(to.id, to.clone());
+            }
+            Edit::AddEntrance(item) => {
+                entrances.insert(item.id, item.clone());
+            }
+            Edit::RemoveEntrance(item) => {
+                entrances.remove(&item.id);
+            }
+            Edit::UpdateEntrance { to, .. } => {
+                // Skip markers removed since, re-adding them would undo the removal
+                if let Some(entrance) = entrances.get_mut(&to.id) {
+                    *entrance = to.clone();
+                }
+            }
+            Edit::SetRowElement { row, column, to, .. } => {
+                row_icons[*row][*column] = to.clone();
+            }
+        }
+    }
+}
+
+/// Undo and redo stacks. Recording a new edit discards anything that could have been redone.
+#[derive(Debug, Default)]
+pub struct History {
+    undo_stack: Vec<Edit>,
+    redo_stack: Vec<Edit>
+}
+
+impl History {
+    pub fn record(&mut self, edit: Edit) {
+        self.undo_stack.push(edit);
+        self.redo_stack.clear();
+    }
+
+    /// Returns the edit that reverts the most recent change, if any.
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo_stack.pop()?;
+        let inverse = edit.inverse();
+        self.redo_stack.push(edit);
+        Some(inverse)
+    }
+
+    /// Returns the edit that re-applies the most recently undone change, if any.
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo_stack.pop()?;
+        self.undo_stack.push(edit.clone());
+        Some(edit)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::Pos2;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct State {
+        entrances: HashMap<Uuid, MapElement>,
+        placed_icons: HashMap<Uuid, MapElement>,
+        row_icons: [[RowElement; ROW_MAX_LENGTH]; 4]
+    }
+
+    impl State {
+        fn new(entrances: &[&MapElement], placed_icons: &[&MapElement]) -> State {
+            State {
+                entrances: entrances.iter().map(|item| (item.id, (*item).clone())).collect(),
+                placed_icons: placed_icons.iter().map(|item| (item.id, (*item).clone())).collect(),
+                row_icons: core::array::from_fn(|_| core::array::from_fn(|_| map_element_kind_to_row_element(MapElementKind::BLANK)))
+            }
+        }
+
+        fn apply(&mut self, edit: &Edit) {
+            edit.apply(&mut self.entrances, &mut self.placed_icons, &mut self.row_icons);
+        }
+    }
+
+    fn element(kind: MapElementKind, x: f32) -> MapElement {
+        MapElement { pos: Pos2::new(x, 10.0), ..map_element_kind_to_map_element(kind) }
+    }
+
+    #[test]
+    fn inverse_reverts_every_edit() {
+        let entrance = element(MapElementKind::ENTRANCE, 10.0);
+        let icon = element(MapElementKind::SPIKE, 10.0);
+        let start = State::new(&[&entrance], &[&icon]);
+        let row = start.row_icons[0][0].clone();
+
+        let edits = vec![
+            Edit::AddPlacedIcon(element(MapElementKind::HYPE, 20.0)),
+            Edit::RemovePlacedIcon(icon.clone()),
+            Edit::MovePlacedIcon { from: icon.clone(), to: MapElement { pos: Pos2::new(30.0, 30.0), ..icon.clone() } },
+            Edit::AddEntrance(element(MapElementKind::ENTRANCE, 40.0)),
+            Edit::RemoveEntrance(entrance.clone()),
+            Edit::UpdateEntrance { from: entrance.clone(), to: MapElement { checked: true, ..entrance.clone() } },
+            Edit::SetRowElement { row: 0, column: 0, from: row.clone(), to: RowElement { state: RowElementState::Crossed, ..row } }
+        ];
+        for edit in edits {
+            let mut state = start.clone();
+            state.apply(&edit);
+            assert_ne!(state, start, "{:?} should change the state", edit);
+            state.apply(&edit.inverse());
+            assert_eq!(state, start, "{:?} should be reverted by its inverse", edit);
+            assert_eq!(edit.inverse().inverse(), edit);
+        }
+    }
+
+    #[test]
+    fn update_entrance_skips_removed_markers() {
+        let entrance = element(MapElementKind::ENTRANCE, 10.0);
+        let mut state = State::new(&[], &[]);
+        state.apply(&Edit::UpdateEntrance { from: entrance.clone(), to: MapElement { checked: true, ..entrance } });
+        assert!(state.entrances.is_empty());
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_stacks() {
+        let first = Edit::AddPlacedIcon(element(MapElementKind::HYPE, 10.0));
+        let second = Edit::AddPlacedIcon(element(MapElementKind::SPIKE, 20.0));
+        let mut history = History::default();
+        assert_eq!(history.undo(), None);
+
+        history.record(first.clone());
+        history.record(second.clone());
+        assert_eq!(history.undo(), Some(second.inverse()));
+        assert!(history.can_redo());
+        assert_eq!(history.redo(), Some(second.clone()));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(second.inverse()));
+        assert_eq!(history.undo(), Some(first.inverse()));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut history = History::default();
+        history.record(Edit::AddPlacedIcon(element(MapElementKind::HYPE, 10.0)));
+        history.undo();
+        assert!(history.can_redo());
+
+        history.record(Edit::AddPlacedIcon(element(MapElementKind::SPIKE, 20.0)));
+        assert!(!history.can_redo());
+        assert_eq!(history.redo(), None);
+    }
+}
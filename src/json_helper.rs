@@ -1,55 +1,189 @@
 use eframe::egui::{self};
 use serde::{Deserialize, Serialize};
-use serde_json::Number;
+use serde_json::Value;
 use uuid::Uuid;
 use std::collections::HashMap;
 
 use crate::map_element;
 use map_element::*;
 
-#[derive(Serialize, Deserialize)]
-struct LocationJson {
-    loc_name: String,
-    kind: String,
-    x: Number,
-    y: Number
-}
+use crate::layout::MAP_SIZE;
+
+/// The 750px entrance map shipped with the tracker, used when no map file is given.
+pub const BUILTIN_MAP: &str = include_str!("../assets/map/750.json");
 
+/// Locations are kept as plain JSON values, so a broken entry can be reported with its world,
+/// index and name instead of failing the whole file.
 #[derive(Serialize, Deserialize)]
 struct LocationsJson {
-    light_world: Vec<LocationJson>,
-    dark_world: Vec<LocationJson>
+    light_world: Vec<Value>,
+    dark_world: Vec<Value>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum World {
+    Light,
+    Dark
+}
+
+impl World {
+    fn key(&self) -> &'static str {
+        match self {
+            World::Light => "light_world",
+            World::Dark => "dark_world"
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MapLoadError {
+    /// The file is not valid JSON or does not have the expected shape.
+    Json(serde_json::Error),
+    /// A single location could not be turned into a map element.
+    Location {
+        world: World,
+        index: usize,
+        loc_name: Option<String>,
+        field: &'static str,
+        problem: String
+    }
+}
+
+impl std::fmt::Display for MapLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapLoadError::Json(err) => write!(f, "Map file is not valid: {}", err),
+            MapLoadError::Location { world, index, loc_name: Some(loc_name), field, problem } => write!(
+                f,
+                "{}[{}] \"{}\": field `{}` {}",
+                world.key(), index, loc_name, field, problem
+            ),
+            MapLoadError::Location { world, index, loc_name: None, field, problem } => write!(
+                f,
+                "{}[{}]: field `{}` {}",
+                world.key(), index, field, problem
+            )
+        }
+    }
+}
+
+impl From<serde_json::Error> for MapLoadError {
+    fn from(err: serde_json::Error) -> Self {
+        MapLoadError::Json(err)
+    }
+}
+
+/// Why a location field is not what the map format expects.
+fn field_problem(value: Option<&Value>, expected: &str) -> String {
+    match value {
+        None => String::from("is missing"),
+        Some(value) => format!("should be {}, found {}", expected, value)
+    }
 }
 
-pub fn load (json_data: &str) -> HashMap<Uuid, map_element::MapElement> {
+fn location_to_element(loc: &Value, world: World, index: usize, x_offset: f32) -> Result<MapElement, MapLoadError> {
+    let loc_name = loc.get("loc_name").and_then(Value::as_str).map(String::from);
+    let location_error = |field: &'static str, problem: String| MapLoadError::Location {
+        world,
+        index,
+        loc_name: loc_name.clone(),
+        field,
+        problem
+    };
+    let string_field = |field: &'static str| loc.get(field).and_then(Value::as_str)
+        .ok_or_else(|| location_error(field, field_problem(loc.get(field), "a string")));
+    let number_field = |field: &'static str| loc.get(field).and_then(Value::as_f64)
+        .ok_or_else(|| location_error(field, field_problem(loc.get(field), "a number")));
+
+    let name = string_field("loc_name")?;
+    let x = number_field("x")?;
+    let y = number_field("y")?;
+    let kind = string_field("kind")?;
+    let kind = str_to_kind(kind).ok_or_else(|| location_error("kind", format!("has unknown kind \"{}\"", kind)))?;
+    let check = match loc.get("check") {
+        None | Some(Value::Null) => None,
+        Some(_) => {
+            let check = string_field("check")?;
+            Some(str_to_kind(check).ok_or_else(|| location_error("check", format!("has unknown kind \"{}\"", check)))?)
+        }
+    };
+
+    Ok(MapElement {
+        pos: egui::Pos2::new(x as f32 + x_offset, y as f32),
+        size: None,
+        kind,
+        id: Uuid::new_v4(),
+        checked: false,
+        check,
+        name: Some(String::from(name)),
+        entrance: None
+    })
+}
+
+pub fn load (json_data: &str) -> Result<HashMap<Uuid, map_element::MapElement>, MapLoadError> {
     let mut items: HashMap<Uuid, MapElement> = HashMap::new();
 
-    let locations: LocationsJson = serde_json::from_str(json_data).expect("Expected JSON");
-    for loc in locations.light_world {
-        let pos = egui::Pos2::new(loc.x.as_f64().unwrap() as f32, loc.y.as_f64().unwrap() as f32);
-
-        let item = MapElement {
-            pos: pos,
-            size: None,
-            kind: str_to_kind(&loc.kind),
-            id: Uuid::new_v4(),
-            checked: false
-        };
+    let locations: LocationsJson = serde_json::from_str(json_data)?;
+    for (index, loc) in locations.light_world.iter().enumerate() {
+        let item = location_to_element(loc, World::Light, index, 0.0)?;
         items.insert(item.id, item);
     }
 
-    for loc in locations.dark_world {
-        let pos = egui::Pos2::new(loc.x.as_f64().unwrap() as f32 + 750.0, loc.y.as_f64().unwrap() as f32);
-
-        let item = map_element::MapElement {
-            pos: pos,
-            size: None,
-            kind: str_to_kind(&loc.kind),
-            id: Uuid::new_v4(),
-            checked: false
-        };
+    for (index, loc) in locations.dark_world.iter().enumerate() {
+        let item = location_to_element(loc, World::Dark, index, MAP_SIZE)?;
         items.insert(item.id, item);
     }
 
-    return items;
-}
\ No newline at end of file
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_error(json_data: &str) -> MapLoadError {
+        load(json_data).expect_err("Map should be rejected")
+    }
+
+    #[test]
+    fn loads_the_builtin_map() {
+        let items = load(BUILTIN_MAP).expect("Built-in map should be valid");
+        assert!(!items.is_empty());
+    }
+
+    #[test]
+    fn reports_unknown_kinds_with_their_location() {
+        let err = load_error(r#"{"light_world": [], "dark_world": [
+            {"loc_name": "Spike Cave", "kind": "ENTRANCE", "x": 1, "y": 2},
+            {"loc_name": "Lumberjacks", "kind": "LUMBERJACKS", "x": 1, "y": 2}
+        ]}"#);
+        match &err {
+            MapLoadError::Location { world, index, loc_name, field, .. } => {
+                assert_eq!(*world, World::Dark);
+                assert_eq!(*index, 1);
+                assert_eq!(loc_name.as_deref(), Some("Lumberjacks"));
+                assert_eq!(*field, "kind");
+            }
+            other => panic!("Expected a location error, got {:?}", other)
+        }
+        assert!(err.to_string().contains("LUMBERJACKS"));
+    }
+
+    #[test]
+    fn reports_bad_fields_with_their_location() {
+        let err = load_error(r#"{"light_world": [{"loc_name": "Link's House", "kind": "ENTRANCE", "x": "12", "y": 2}], "dark_world": []}"#);
+        assert_eq!(err.to_string(), r#"light_world[0] "Link's House": field `x` should be a number, found "12""#);
+
+        let err = load_error(r#"{"light_world": [{"loc_name": "Link's House", "kind": "ENTRANCE", "x": 12}], "dark_world": []}"#);
+        assert_eq!(err.to_string(), r#"light_world[0] "Link's House": field `y` is missing"#);
+
+        let err = load_error(r#"{"light_world": [{"kind": "ENTRANCE", "x": 12, "y": 2}], "dark_world": []}"#);
+        assert_eq!(err.to_string(), "light_world[0]: field `loc_name` is missing");
+    }
+
+    #[test]
+    fn reports_invalid_json() {
+        assert!(matches!(load_error(r#"{"light_world": ["#), MapLoadError::Json(_)));
+        assert!(matches!(load_error(r#"{"light_world": []}"#), MapLoadError::Json(_)));
+    }
+}
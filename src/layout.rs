@@ -1,6 +1,19 @@
 use crate::map_element;
 use map_element::MapElementKind;
 
+/// Width and height of one world map in map-space. The dark world sits to the right of the light world.
+pub const MAP_SIZE: f32 = 750.0;
+
+/// Size of the whole tracker at a scale of 1.0: both maps side by side above the rows.
+pub const DESIGN_WIDTH: f32 = 1500.0;
+pub const DESIGN_HEIGHT: f32 = 950.0;
+
+pub const ROW_X_START: f32 = 26.0;
+pub const ROW_Y_START: f32 = 776.0;
+pub const ROW_OFFSET: f32 = 49.0;
+pub const ROW_ICON_SIZE: f32 = 44.0;
+pub const CONTROLS_X_START: f32 = 1150.0;
+
 pub const ROW_MAX_LENGTH: usize = 23;
 pub const ROW1: [MapElementKind; ROW_MAX_LENGTH] = [
     MapElementKind::ZELDA, 
@@ -3,23 +3,85 @@
 use eframe::egui::{self, Color32, Vec2};
 use layout::ROW_MAX_LENGTH;
 use uuid::Uuid;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 mod map_element;
 use map_element::*;
 
 mod json_helper;
 mod layout;
+mod registry;
 
 mod row_element;
 use row_element::*;
 
 mod autotracker;
+use autotracker::{Autotracker, AutotrackerEvent, AutotrackerStatus};
+mod memory_source;
+use memory_source::{MemorySource, MemorySourceKind};
+mod retroarch;
+use retroarch::RetroArchSource;
+mod srm_file;
+use srm_file::SrmFileSource;
+mod usb2snes;
+use usb2snes::Usb2SnesSource;
+#[cfg(test)]
+mod mock_usb2snes;
+
+mod view;
+use view::{MapView, ScreenLayout};
+
+mod history;
+use history::{Edit, History};
+
+mod game_state;
+use game_state::Transition;
+
+mod sram;
+use sram::{CheckStatus, LevelChange, StatusChange};
+
+mod session;
+mod settings;
+use settings::Settings;
+
+#[derive(Default)]
+struct Args {
+    map_path: Option<PathBuf>,
+    kinds_path: Option<PathBuf>
+}
+
+/// Accepts `--map <file>` (or the map file as a bare argument) and `--kinds <manifest>`.
+fn parse_args() -> Args {
+    let mut parsed = Args::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--map" {
+            parsed.map_path = args.next().map(PathBuf::from);
+        } else if arg == "--kinds" {
+            parsed.kinds_path = args.next().map(PathBuf::from);
+        } else if !arg.starts_with('-') && parsed.map_path.is_none() {
+            parsed.map_path = Some(PathBuf::from(arg));
+        }
+    }
+
+    parsed
+}
 
 fn main() -> Result<(), eframe::Error> {
     env_logger::init();
+    let args = parse_args();
+
+    // Without --kinds, a manifest in the data directory overrides the built-in kinds
+    let kinds_path = args.kinds_path
+        .or_else(|| Some(session::data_dir().join(registry::MANIFEST_FILE)).filter(|path| path.exists()));
+    let startup_error = registry::init(kinds_path.as_deref()).err();
+    let map_path = args.map_path;
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([1500.0, 950.0]),
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([layout::DESIGN_WIDTH, layout::DESIGN_HEIGHT])
+            .with_min_inner_size([layout::DESIGN_WIDTH / 3.0, layout::DESIGN_HEIGHT / 3.0]),
         ..Default::default()
     };
     eframe::run_native(
@@ -27,37 +89,785 @@ fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Box::new(MyApp::new())
+            Box::new(MyApp::new(map_path, startup_error))
         }),
     )
 }
 
+/// Entrance markers this close to where Link was last seen outside count as the one he used.
+const LINK_RADIUS: f32 = 40.0;
+/// A second marker less than this much further away than the closest one makes the guess too close to call.
+const LINK_MARGIN: f32 = 10.0;
+/// Icons dropped this close to an entrance marker snap onto it and are linked to it.
+const SNAP_RADIUS: f32 = 15.0;
+/// Size of icons placed on the map, whether dragged from the rows or placed by the autotracker.
+const PLACED_ICON_SIZE: f32 = 25.0;
+
+struct LinkSuggestion {
+    entrance: Uuid,
+    destination: MapElementKind
+}
+
+/// Spreads the connectors around the colour wheel so neighbouring ones are told apart.
+fn connector_color(index: usize) -> Color32 {
+    let hue = (index as f32 * 0.618).fract();
+    egui::ecolor::Hsva::new(hue, 0.8, 1.0, 1.0).into()
+}
+
+/// Markers whose name or linked destination contains `search`, left to right across both maps.
+fn search_hits<'a>(
+    search: &str,
+    entrances: &'a HashMap<Uuid, MapElement>,
+    placed_icons: &HashMap<Uuid, MapElement>
+) -> Vec<&'a MapElement> {
+    let search = search.trim().to_lowercase();
+    if search.is_empty() {
+        return Vec::new();
+    }
+
+    let matches = |kind: MapElementKind| registry::registry().info(kind).name.to_lowercase().contains(&search);
+    let mut hits: Vec<&MapElement> = entrances.values()
+        .filter(|entrance| {
+            entrance.name.as_ref().is_some_and(|name| name.to_lowercase().contains(&search))
+                || entrance.check.is_some_and(matches)
+                || placed_icons.values().any(|icon| icon.entrance == Some(entrance.id) && matches(icon.kind))
+        })
+        .collect();
+    hits.sort_by(|a, b| a.pos.x.total_cmp(&b.pos.x).then(a.pos.y.total_cmp(&b.pos.y)));
+    hits
+}
+
 struct MyApp {
     entrances: HashMap<Uuid, MapElement>,
     placed_icons: HashMap<Uuid, MapElement>,
     row_icons: [[RowElement; ROW_MAX_LENGTH]; 4],
-    drag_element: Option<MapElement>
+    drag_element: Option<MapElement>,
+    map_path: Option<PathBuf>,
+    view: MapView,
+    history: History,
+    autotracker: Option<Autotracker>,
+    /// Why the autotracker could not be started the last time, shown until it starts.
+    autotracker_error: Option<String>,
+    /// Destination the autotracker offers to place after Link went through an entrance.
+    link_suggestion: Option<LinkSuggestion>,
+    /// Text in the entrance search box, matching markers are highlighted on the map.
+    search: String,
+    settings: Settings,
+    status_message: Option<String>,
+    error_dialog: Option<String>,
+    dirty: bool
+}
+
+fn default_row_icons() -> [[RowElement; ROW_MAX_LENGTH]; 4] {
+    [
+        core::array::from_fn(|i| map_element_kind_to_row_element(layout::ROW1[i])),
+        core::array::from_fn(|i| map_element_kind_to_row_element(layout::ROW2[i])),
+        core::array::from_fn(|i| map_element_kind_to_row_element(layout::ROW3[i])),
+        core::array::from_fn(|i| map_element_kind_to_row_element(layout::ROW4[i]))
+    ]
 }
 
 impl MyApp {
-    fn new() -> Self {
-        let data = include_str!("../assets/map/750.json");
-        return MyApp {
-            entrances: json_helper::load(data),
+    fn new(map_override: Option<PathBuf>, startup_error: Option<String>) -> Self {
+        let mut app = MyApp {
+            entrances: HashMap::new(),
             placed_icons: HashMap::new(),
-            row_icons: [
-                core::array::from_fn(|i| map_element_kind_to_row_element(layout::ROW1[i])),
-                core::array::from_fn(|i| map_element_kind_to_row_element(layout::ROW2[i])),
-                core::array::from_fn(|i| map_element_kind_to_row_element(layout::ROW3[i])),
-                core::array::from_fn(|i| map_element_kind_to_row_element(layout::ROW4[i]))
-            ],
-            drag_element: None
+            row_icons: default_row_icons(),
+            drag_element: None,
+            map_path: None,
+            view: MapView::default(),
+            history: History::default(),
+            autotracker: None,
+            autotracker_error: None,
+            link_suggestion: None,
+            search: String::new(),
+            settings: settings::load(),
+            status_message: None,
+            error_dialog: startup_error,
+            dirty: false
+        };
+
+        let settings_map = Some(PathBuf::from(&app.settings.map_path)).filter(|path| !path.as_os_str().is_empty());
+        app.load_map(settings_map);
+
+        if app.settings.restore_last_session {
+            let path = session::last_session_path();
+            if path.exists() {
+                match session::load(&path) {
+                    Ok(restored) => app.apply_session(restored),
+                    Err(err) => app.status_message = Some(format!("Could not restore last session: {}", err))
+                }
+            }
+        }
+
+        // A map given on the command line wins over the entrances stored in the restored session
+        if map_override.is_some() {
+            app.load_map(map_override);
+        }
+
+        app
+    }
+
+    /// Replaces the entrance markers with the ones from `path`, or the built-in map if `None`.
+    /// A file that cannot be read or parsed is reported and the built-in map is used instead.
+    fn load_map(&mut self, path: Option<PathBuf>) {
+        let loaded = match &path {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|err| format!("Unable to read map {}: {}", path.display(), err))
+                .and_then(|data| json_helper::load(&data)
+                    .map_err(|err| format!("Unable to load map {}:\n{}", path.display(), err))),
+            None => Ok(json_helper::load(json_helper::BUILTIN_MAP).expect("Built-in map should be valid"))
+        };
+
+        match loaded {
+            Ok(entrances) => {
+                if let Some(path) = &path {
+                    self.status_message = Some(format!("Loaded map {}", path.display()));
+                }
+                self.entrances = entrances;
+                self.map_path = path;
+            }
+            Err(err) => {
+                self.error_dialog = Some(format!("{}\n\nUsing the built-in map instead.", err));
+                self.entrances = json_helper::load(json_helper::BUILTIN_MAP).expect("Built-in map should be valid");
+                self.map_path = None;
+            }
+        }
+        self.settings.map_path = self.map_path.as_ref().map(|path| path.display().to_string()).unwrap_or_default();
+
+        // Linked icons sit on their entrance, so they find it again among the new markers
+        let relinked: Vec<(Uuid, Option<Uuid>)> = self.placed_icons.values()
+            .filter(|icon| icon.entrance.is_some())
+            .map(|icon| (icon.id, self.snap_entrance(icon.pos).map(|entrance| entrance.id)))
+            .collect();
+        for (id, entrance) in relinked {
+            if let Some(icon) = self.placed_icons.get_mut(&id) {
+                icon.entrance = entrance;
+            }
+        }
+
+        // Entrance ids change on every load, so older edits can no longer be undone
+        self.history.clear();
+        self.dirty = true;
+    }
+
+    fn reload_map(&mut self) {
+        self.load_map(self.map_path.clone());
+    }
+
+    fn apply_session(&mut self, restored: session::Session) {
+        // Sessions from before names were saved take them from the matching markers of the loaded map
+        let mut entrances = restored.entrances;
+        for entrance in entrances.values_mut().filter(|entrance| entrance.name.is_none()) {
+            entrance.name = self.entrances.values()
+                .find(|known| known.kind == entrance.kind && known.pos == entrance.pos)
+                .and_then(|known| known.name.clone());
+        }
+
+        self.entrances = entrances;
+        self.placed_icons = restored.placed_icons;
+        self.row_icons = restored.row_icons;
+        self.drag_element = None;
+        self.history.clear();
+    }
+
+    /// Applies a user edit and records it so it can be undone.
+    fn perform(&mut self, edit: Edit) {
+        self.apply_edit(&edit);
+        self.history.record(edit);
+    }
+
+    /// Applies a change coming from the autotracker. It is not the user's edit, so it stays out of
+    /// the undo history and leaves redo alone; undoing it would only have it set again on the next poll.
+    fn apply_tracked(&mut self, edit: Edit) {
+        self.apply_edit(&edit);
+    }
+
+    fn undo(&mut self) {
+        if let Some(edit) = self.history.undo() {
+            self.apply_edit(&edit);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(edit) = self.history.redo() {
+            self.apply_edit(&edit);
+        }
+    }
+
+    fn apply_edit(&mut self, edit: &Edit) {
+        edit.apply(&mut self.entrances, &mut self.placed_icons, &mut self.row_icons);
+        self.dirty = true;
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // Text fields have their own undo
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        // Check the shifted shortcut first, consume_key ignores extra modifiers
+        let redo_pressed = ctx.input_mut(|i| {
+            i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)
+                || i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y)
+        });
+        let undo_pressed = ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z));
+
+        if redo_pressed {
+            self.redo();
+        } else if undo_pressed {
+            self.undo();
+        }
+    }
+
+    fn session_json(&self) -> String {
+        session::to_json(&self.entrances, &self.placed_icons, &self.row_icons)
+    }
+
+    fn new_session(&mut self) {
+        self.reload_map();
+        self.placed_icons = HashMap::new();
+        self.row_icons = default_row_icons();
+        self.drag_element = None;
+        self.history.clear();
+        self.dirty = true;
+        self.status_message = Some(String::from("Started a new session"));
+    }
+
+    fn save_session(&mut self) {
+        let path = std::path::PathBuf::from(&self.settings.session_path);
+        self.status_message = Some(match session::save(&path, &self.session_json()) {
+            Ok(()) => format!("Saved session to {}", path.display()),
+            Err(err) => err.to_string()
+        });
+        settings::save(&self.settings);
+    }
+
+    fn load_session(&mut self) {
+        let path = std::path::PathBuf::from(&self.settings.session_path);
+        match session::load(&path) {
+            Ok(restored) => {
+                self.apply_session(restored);
+                self.dirty = true;
+                self.status_message = Some(format!("Loaded session from {}", path.display()));
+            }
+            Err(err) => self.status_message = Some(err.to_string())
+        }
+        settings::save(&self.settings);
+    }
+
+    /// Keep the last-session file current so a crash mid-race loses nothing.
+    fn autosave(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        if let Err(err) = session::save(&session::last_session_path(), &self.session_json()) {
+            log::warn!("Unable to autosave session: {}", err);
+        }
+    }
+
+    fn start_autotracking(&mut self, ctx: &egui::Context) {
+        let poll_interval = Duration::from_millis(self.settings.poll_interval_ms);
+        let ctx = ctx.clone();
+        let source: Result<Box<dyn MemorySource>, String> = match self.settings.memory_source {
+            MemorySourceKind::Usb2snes => Usb2SnesSource::new(&self.settings.autotracker_url)
+                .map(|source| Box::new(source) as Box<dyn MemorySource>),
+            MemorySourceKind::RetroArch => Ok(Box::new(RetroArchSource::new(&self.settings.retroarch_address))),
+            MemorySourceKind::SrmFile => Ok(Box::new(SrmFileSource::new(Path::new(&self.settings.srm_path))))
+        };
+        let started = source.and_then(|source| Autotracker::start(
+            source,
+            poll_interval,
+            self.settings.autotracker_device.clone(),
+            move || ctx.request_repaint()
+        ));
+        match started {
+            Ok(tracker) => {
+                self.autotracker = Some(tracker);
+                self.autotracker_error = None;
+            }
+            Err(err) => {
+                self.autotracker_error = Some(err.clone());
+                self.error_dialog = Some(err);
+            }
+        }
+    }
+
+    fn stop_autotracking(&mut self) {
+        self.autotracker = None;
+    }
+
+    /// Applies the changes reported by the autotracker. They stay out of the undo history, so
+    /// polling never clears what can be redone.
+    fn process_autotracking(&mut self) {
+        let Some(tracker) = &mut self.autotracker else { return; };
+        for event in tracker.poll_events() {
+            match event {
+                AutotrackerEvent::ChecksChanged(changes) => {
+                    for change in changes {
+                        self.apply_check_change(change);
+                    }
+                }
+                AutotrackerEvent::InventoryChanged(changes) => {
+                    for change in changes {
+                        self.apply_inventory_change(change);
+                    }
+                }
+                AutotrackerEvent::Transition(transition) => self.link_transition(transition),
+                _ => ()
+            }
+        }
+    }
+
+    /// Applies everything collected in a save file once, e.g. to rebuild a finished race.
+    fn apply_srm_file(&mut self) {
+        match srm_file::load_savedata(Path::new(&self.settings.srm_path)) {
+            Ok(savedata) => {
+                let blank = vec![0; savedata.len()];
+                for change in sram::diff(&blank, &savedata) {
+                    self.apply_check_change(change);
+                }
+                for change in sram::diff_inventory(&blank, &savedata) {
+                    self.apply_inventory_change(change);
+                }
+                self.status_message = Some(format!("Applied {}", self.settings.srm_path));
+            }
+            Err(err) => self.error_dialog = Some(format!("Unable to read save file {}: {}", self.settings.srm_path, err))
+        }
+    }
+
+    fn apply_check_change(&mut self, change: StatusChange) {
+        // Only ever move forward, a reset or a bad read must not undo progress
+        if change.new < change.old {
+            return;
+        }
+
+        if change.new == CheckStatus::Complete {
+            self.check_off_markers(change.kind);
+        }
+
+        // Row icons of inventory items show whether the item is owned, not where it was found,
+        // but their location markers, like the powder at the witch's hut, are still checked off
+        if !sram::is_inventory_kind(change.kind) {
+            self.update_check_rows(change);
+        }
+    }
+
+    /// Marks the ITEM markers standing for a collected check as collected.
+    fn check_off_markers(&mut self, kind: MapElementKind) {
+        let collected: Vec<MapElement> = self.entrances.values()
+            .filter(|item| item.check == Some(kind) && !item.checked)
+            .cloned()
+            .collect();
+        for item in collected {
+            self.apply_tracked(Edit::UpdateEntrance { from: item.clone(), to: MapElement { checked: true, ..item } });
+        }
+    }
+
+    fn update_check_rows(&mut self, change: StatusChange) {
+        let Some(state) = autotracked_state(change.new) else { return; };
+        for row in 0..self.row_icons.len() {
+            for column in 0..ROW_MAX_LENGTH {
+                let item = &self.row_icons[row][column];
+                if item.kind != change.kind || item.manual_override || item.state == state {
+                    continue;
+                }
+
+                let to = RowElement { state: state.clone(), ..item.clone() };
+                self.apply_tracked(Edit::SetRowElement { row, column, from: item.clone(), to });
+            }
+        }
+    }
+
+    fn apply_inventory_change(&mut self, change: LevelChange) {
+        // Same as checks, losing an item is far more likely a bad read than a real downgrade
+        if change.new <= change.old {
+            return;
+        }
+
+        for row in 0..self.row_icons.len() {
+            for column in 0..ROW_MAX_LENGTH {
+                let item = &self.row_icons[row][column];
+                if item.kind != change.kind || item.manual_override {
+                    continue;
+                }
+                if item.state == RowElementState::Owned && item.level == change.new {
+                    continue;
+                }
+
+                let to = RowElement { state: RowElementState::Owned, level: change.new, ..item.clone() };
+                self.apply_tracked(Edit::SetRowElement { row, column, from: item.clone(), to });
+            }
+        }
+    }
+
+    /// Offers to place what is behind the door Link used on the entrance marker he was last seen at.
+    /// The entrance id names the destination, but not the door, so the marker still comes from
+    /// Link's position and is only placed without asking when no other marker is about as close.
+    fn link_transition(&mut self, transition: Transition) {
+        let Some(destination) = transition.destination() else { return; };
+        let mut candidates: Vec<(Uuid, f32)> = self.entrances.values()
+            .filter(|item| item.kind == MapElementKind::ENTRANCE)
+            .map(|item| (item.id, item.pos.distance(transition.from)))
+            .filter(|(_, distance)| *distance <= LINK_RADIUS)
+            .collect();
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((entrance, distance)) = candidates.first().copied() else { return; };
+        let already_linked = self.placed_icons.values()
+            .any(|icon| icon.kind == destination && icon.entrance == Some(entrance));
+        if already_linked {
+            return;
+        }
+
+        let ambiguous = candidates.get(1).is_some_and(|(_, next)| next - distance < LINK_MARGIN);
+
+        let suggestion = LinkSuggestion { entrance, destination };
+        if self.settings.auto_place_links && !ambiguous {
+            self.accept_link(suggestion);
+        } else {
+            self.link_suggestion = Some(suggestion);
+        }
+    }
+
+    fn accept_link(&mut self, suggestion: LinkSuggestion) {
+        let Some(entrance) = self.entrances.get(&suggestion.entrance) else { return; };
+        let mut icon = map_element_kind_to_map_element(suggestion.destination);
+        icon.size = Some(Vec2::new(PLACED_ICON_SIZE, PLACED_ICON_SIZE));
+        let edit = self.place_icon(icon, entrance.pos);
+        self.perform(edit);
+    }
+
+    /// The entrance marker within `SNAP_RADIUS` of `pos`, closest first.
+    fn snap_entrance(&self, pos: egui::Pos2) -> Option<&MapElement> {
+        self.entrances.values()
+            .filter(|item| item.kind == MapElementKind::ENTRANCE)
+            .map(|item| (item, item.pos.distance(pos)))
+            .filter(|(_, distance)| *distance <= SNAP_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(item, _)| item)
+    }
+
+    /// Placing a unique kind that is already on the map moves it instead of placing it twice.
+    /// Icons dropped on an entrance snap onto it and remember it as the way in.
+    fn place_icon(&self, icon: MapElement, pos: egui::Pos2) -> Edit {
+        let (pos, entrance) = match self.snap_entrance(pos) {
+            Some(entrance) => (entrance.pos, Some(entrance.id)),
+            None => (pos, None)
+        };
+
+        let existing = self.placed_icons.get(&icon.id)
+            .or_else(|| self.placed_icons.values().find(|v| kind_is_unique(v.kind) && v.kind == icon.kind));
+        match existing {
+            Some(from) => Edit::MovePlacedIcon { from: from.clone(), to: MapElement { pos, entrance, ..from.clone() } },
+            None => Edit::AddPlacedIcon(MapElement { pos, entrance, ..icon })
+        }
+    }
+
+    /// Hover text for a map marker: its name, its check and where it leads.
+    fn marker_tooltip(&self, entrance: &MapElement) -> String {
+        let mut lines = vec![entrance.name.clone().unwrap_or_else(|| registry::registry().info(entrance.kind).name.clone())];
+        if let Some(check) = entrance.check {
+            lines.push(format!("Check: {}", registry::registry().info(check).name));
+        }
+
+        let destination = self.placed_icons.values().find(|icon| icon.entrance == Some(entrance.id));
+        if let Some(destination) = destination {
+            lines.push(format!("Leads to: {}", registry::registry().info(destination.kind).name));
+        }
+
+        lines.join("\n")
+    }
+
+    fn search_hits(&self) -> Vec<&MapElement> {
+        search_hits(&self.search, &self.entrances, &self.placed_icons)
+    }
+
+    fn search_panel(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            let response = ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("entrance or destination").desired_width(150.0));
+            let hits = self.search_hits();
+            if !self.search.trim().is_empty() {
+                ui.label(format!("{} found", hits.len()));
+            }
+
+            let first = hits.first().map(|hit| hit.pos);
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                if let Some(pos) = first {
+                    self.view.focus_on(pos);
+                }
+            }
+            if !self.search.is_empty() && ui.button("Clear").clicked() {
+                self.search.clear();
+            }
+        });
+    }
+
+    fn link_suggestion_panel(&mut self, ui: &mut egui::Ui) {
+        let Some(suggestion) = &self.link_suggestion else { return; };
+        let name = &registry::registry().info(suggestion.destination).name;
+        ui.label(format!("Link went into {}.", name));
+        ui.horizontal(|ui| {
+            if ui.button("Place icon").clicked() {
+                if let Some(suggestion) = self.link_suggestion.take() {
+                    self.accept_link(suggestion);
+                }
+            }
+            if ui.button("Dismiss").clicked() {
+                self.link_suggestion = None;
+            }
+        });
+    }
+
+    /// Joins the placed ends of every connector in member order, each connector in a colour of its own.
+    /// Ends on different worlds are joined straight across the two maps.
+    fn draw_connectors(&self, ui: &egui::Ui, screen: &ScreenLayout) {
+        for (index, connector) in registry::registry().connectors().iter().enumerate() {
+            let ends: Vec<egui::Pos2> = connector.members.iter()
+                .filter_map(|kind| self.placed_icons.values().find(|placed| placed.kind == *kind))
+                .map(|placed| self.view.map_to_screen(screen, placed.pos))
+                .collect();
+            let stroke = egui::Stroke::new(3.0 * screen.scale, connector_color(index));
+            for pair in ends.windows(2) {
+                ui.painter().line_segment([pair[0], pair[1]], stroke);
+            }
+        }
+    }
+
+    /// Link's live position, with a ring around the entrance marker closest to him.
+    fn draw_link_marker(&self, ui: &egui::Ui, screen: &ScreenLayout) {
+        let Some(pos) = self.autotracker.as_ref().and_then(|tracker| tracker.info().link_pos) else { return; };
+        let map_scale = self.view.scale(screen);
+
+        // Only markers on the same world, the other map's edge can be closer
+        let dark_world = pos.x >= layout::MAP_SIZE;
+        let nearest = self.entrances.values()
+            .filter(|item| item.kind == MapElementKind::ENTRANCE && (item.pos.x >= layout::MAP_SIZE) == dark_world)
+            .min_by(|a, b| a.pos.distance(pos).total_cmp(&b.pos.distance(pos)));
+        if let Some(nearest) = nearest {
+            let center = self.view.map_to_screen(screen, nearest.pos);
+            ui.painter().circle_stroke(center, 12.0 * map_scale, egui::Stroke::new(3.0, Color32::LIGHT_BLUE));
+        }
+
+        let center = self.view.map_to_screen(screen, pos);
+        ui.painter().circle(center, 6.0 * map_scale, Color32::from_rgb(40, 180, 60), egui::Stroke::new(2.0, Color32::WHITE));
+    }
+
+    fn show_error_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        if let Some(message) = &self.error_dialog {
+            egui::Window::new("Error")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(message);
+                    if ui.button("OK").clicked() {
+                        open = false;
+                    }
+                });
+        }
+
+        if !open {
+            self.error_dialog = None;
+        }
+    }
+
+    fn autotracker_panel(&mut self, ui: &mut egui::Ui) {
+        let connected = self.autotracker.is_some();
+        ui.add_enabled_ui(!connected, |ui| {
+            ui.horizontal(|ui| {
+                let usb2snes = ui.radio_value(&mut self.settings.memory_source, MemorySourceKind::Usb2snes, "usb2snes/SNI");
+                let retroarch = ui.radio_value(&mut self.settings.memory_source, MemorySourceKind::RetroArch, "RetroArch");
+                let srm_file = ui.radio_value(&mut self.settings.memory_source, MemorySourceKind::SrmFile, "Save file");
+                if usb2snes.changed() || retroarch.changed() || srm_file.changed() {
+                    settings::save(&self.settings);
+                }
+            });
+            ui.horizontal(|ui| {
+                let address = match self.settings.memory_source {
+                    MemorySourceKind::Usb2snes => {
+                        ui.label("URL:");
+                        &mut self.settings.autotracker_url
+                    }
+                    MemorySourceKind::RetroArch => {
+                        ui.label("Address:");
+                        &mut self.settings.retroarch_address
+                    }
+                    MemorySourceKind::SrmFile => {
+                        ui.label(".srm file:");
+                        &mut self.settings.srm_path
+                    }
+                };
+                if ui.text_edit_singleline(address).lost_focus() {
+                    settings::save(&self.settings);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Poll every");
+                let interval = egui::DragValue::new(&mut self.settings.poll_interval_ms).clamp_range(100..=10000).suffix(" ms");
+                let response = ui.add(interval);
+                if response.drag_released() || response.lost_focus() {
+                    settings::save(&self.settings);
+                }
+            });
+        });
+
+        let Some(tracker) = &self.autotracker else {
+            ui.label("Status: disconnected");
+            if let Some(err) = &self.autotracker_error {
+                ui.colored_label(Color32::LIGHT_RED, format!("Last error: {}", err));
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Connect").clicked() {
+                    self.start_autotracking(ui.ctx());
+                }
+                if self.settings.memory_source == MemorySourceKind::SrmFile && ui.button("Apply once").clicked() {
+                    self.apply_srm_file();
+                }
+            });
+            return;
+        };
+
+        let info = tracker.info().clone();
+        let status = match info.status {
+            AutotrackerStatus::Disconnected => String::from("disconnected"),
+            AutotrackerStatus::Reconnecting => {
+                let wait = info.retry_at.map(|time| time.saturating_duration_since(Instant::now())).unwrap_or_default();
+                // Nothing else happens while waiting, so keep the countdown moving
+                ui.ctx().request_repaint_after(Duration::from_secs(1));
+                format!("reconnecting in {:.0}s", wait.as_secs_f32().ceil())
+            }
+            AutotrackerStatus::Connecting => String::from("connecting"),
+            AutotrackerStatus::Connected => String::from("connected, waiting for the game"),
+            AutotrackerStatus::Tracking => String::from("tracking")
+        };
+        ui.label(format!("Status: {}", status));
+
+        if info.devices.len() > 1 {
+            let mut picked: Option<String> = None;
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Device")
+                    .selected_text(info.device.clone().unwrap_or_else(|| String::from("Pick a device")))
+                    .show_ui(ui, |ui| {
+                        for device in &info.devices {
+                            if ui.selectable_label(info.device.as_ref() == Some(device), device).clicked() {
+                                picked = Some(device.clone());
+                            }
+                        }
+                    });
+                if info.status == AutotrackerStatus::Connecting && ui.button("Refresh").clicked() {
+                    tracker.refresh_devices();
+                }
+            });
+            if let Some(device) = picked {
+                tracker.attach(device.clone());
+                self.settings.autotracker_device = Some(device);
+                settings::save(&self.settings);
+            }
+        } else if let Some(device) = &info.device {
+            ui.label(format!("Device: {}", device));
+        }
+
+        let last_poll = match info.last_poll {
+            Some(time) => {
+                // Polls without changes do not wake the UI, so keep the age ticking
+                ui.ctx().request_repaint_after(Duration::from_secs(1));
+                format!("{}s ago", time.elapsed().as_secs())
+            }
+            None => String::from("never")
         };
+        ui.label(format!("Last poll: {}", last_poll));
+        if let Some(err) = &info.error {
+            ui.colored_label(Color32::LIGHT_RED, format!("Last error: {}", err));
+        }
+
+        if ui.button("Disconnect").clicked() {
+            self.stop_autotracking();
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui, rect: egui::Rect) {
+        let mut controls_ui = ui.child_ui(rect.shrink(4.0), egui::Layout::top_down(egui::Align::Min));
+        egui::ScrollArea::vertical().show(&mut controls_ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("New").clicked() {
+                    self.new_session();
+                }
+                if ui.button("Save").clicked() {
+                    self.save_session();
+                }
+                if ui.button("Load").clicked() {
+                    self.load_session();
+                }
+
+                ui.separator();
+
+                if ui.add_enabled(self.history.can_undo(), egui::Button::new("Undo")).clicked() {
+                    self.undo();
+                }
+                if ui.add_enabled(self.history.can_redo(), egui::Button::new("Redo")).clicked() {
+                    self.redo();
+                }
+            });
+
+            self.search_panel(ui);
+
+            ui.horizontal(|ui| {
+                ui.label("Session file:");
+                ui.text_edit_singleline(&mut self.settings.session_path);
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Map file:");
+                ui.add(egui::TextEdit::singleline(&mut self.settings.map_path).hint_text("built-in").desired_width(150.0));
+                if ui.button("Load map").clicked() {
+                    let path = Some(PathBuf::from(&self.settings.map_path)).filter(|path| !path.as_os_str().is_empty());
+                    self.load_map(path);
+                    settings::save(&self.settings);
+                }
+                if ui.button("Reload").clicked() {
+                    self.reload_map();
+                }
+            });
+
+            if ui.checkbox(&mut self.settings.hide_collected_items, "Hide collected items").changed() {
+                settings::save(&self.settings);
+            }
+
+            if ui.checkbox(&mut self.settings.show_connectors, "Connect the ends of caves and dungeons").changed() {
+                settings::save(&self.settings);
+            }
+
+            if ui.checkbox(&mut self.settings.restore_last_session, "Restore last session on startup").changed() {
+                settings::save(&self.settings);
+            }
+
+            egui::CollapsingHeader::new("Autotracker").default_open(true).show(ui, |ui| {
+                self.autotracker_panel(ui);
+                if ui.checkbox(&mut self.settings.auto_place_links, "Place icons behind entrances automatically").changed() {
+                    settings::save(&self.settings);
+                }
+                if ui.checkbox(&mut self.settings.show_link_position, "Show Link on the map").changed() {
+                    settings::save(&self.settings);
+                }
+                self.link_suggestion_panel(ui);
+            });
+
+            if let Some(message) = &self.status_message {
+                ui.label(message);
+            }
+        });
     }
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.handle_shortcuts(ctx);
+        self.process_autotracking();
+
         let my_frame = egui::containers::Frame {
             inner_margin: egui::style::Margin { left: 0.0, right: 0.0, top: 0.0, bottom: 0.0 },
             outer_margin: egui::style::Margin { left: 0.0, right: 0.0, top: 0.0, bottom: 0.0 },
@@ -68,145 +878,243 @@ impl eframe::App for MyApp {
         };
 
         egui::CentralPanel::default().frame(my_frame).show(ctx, |ui| {
-            let map_size = egui::Vec2::new(750.0, 750.0);
+            let screen = ScreenLayout::new(ui.max_rect());
+            let mut edits: Vec<Edit> = Vec::new();
+
+            // Everything on the maps is drawn in a child ui clipped to the map area so zooming never spills over the rows
+            let mut map_ui = ui.child_ui(screen.map_rect, *ui.layout());
+            map_ui.set_clip_rect(screen.map_rect);
+
+            let background = map_ui.interact(screen.map_rect, map_ui.id().with("map_background"), egui::Sense::click_and_drag());
+            if background.dragged() && self.drag_element.is_none() {
+                self.view.pan_by(&screen, background.drag_delta());
+            }
+            if background.double_clicked() {
+                self.view = MapView::default();
+            }
+            if let Some(hover_pos) = background.hover_pos() {
+                // Plain wheel and pinch / ctrl+wheel both zoom the map
+                let factor = ui.input(|i| (i.smooth_scroll_delta.y / 200.0).exp() * i.zoom_delta());
+                if factor != 1.0 {
+                    self.view.zoom_at(&screen, hover_pos, factor);
+                }
+            }
+
+            let map_size = egui::Vec2::new(layout::MAP_SIZE, layout::MAP_SIZE);
             let rect = egui::Rect::from_min_size(egui::Pos2::new(0.0, 0.0), map_size);
-            ui.put(rect, egui::Image::new(egui::include_image!("../assets/lightworld750.png")));
+            map_ui.put(self.view.map_rect_to_screen(&screen, rect), egui::Image::new(egui::include_image!("../assets/lightworld750.png")));
+
+            let rect = egui::Rect::from_min_size(egui::Pos2::new(layout::MAP_SIZE, 0.0), map_size);
+            map_ui.put(self.view.map_rect_to_screen(&screen, rect), egui::Image::new(egui::include_image!("../assets/darkworld750.png")));
+
+            let map_scale = self.view.scale(&screen);
+            for item in self.entrances.values() {
+                if item.checked && self.settings.hide_collected_items { continue; }
+
+                let icon_size = egui::Vec2::new(15.0, 15.0) * map_scale;
+                let rect = egui::Rect::from_center_size(self.view.map_to_screen(&screen, item.pos), icon_size);
+                
+                let widget = egui::Image::new(kind_to_source(item.kind))
+                    .sense(egui::Sense::click())
+                    .tint(Color32::from_white_alpha(if item.checked { 60 } else { 255 }));
+
+                let response = map_ui.put(rect, widget).on_hover_text(self.marker_tooltip(item));
+                if response.secondary_clicked() {
+                    edits.push(Edit::RemoveEntrance(item.clone()));
+                }
+                if self.link_suggestion.as_ref().is_some_and(|suggestion| suggestion.entrance == item.id) {
+                    map_ui.painter().circle_stroke(rect.center(), rect.width(), egui::Stroke::new(3.0, Color32::YELLOW));
+                }
+            }
 
-            let rect = egui::Rect::from_min_size(egui::Pos2::new(750.0, 0.0), map_size);
-            ui.put(rect, egui::Image::new(egui::include_image!("../assets/darkworld750.png")));
+            if self.settings.show_connectors {
+                self.draw_connectors(&map_ui, &screen);
+            }
 
-            let x_start = 26.0;
-            let y_start = 776.0;
-            let offset = 49.0;
-            let icon_size = egui::Vec2::new(44.0, 44.0);
+            for item in self.placed_icons.values() {
+                // The icon being dragged is drawn under the pointer instead
+                if self.drag_element.as_ref().is_some_and(|drag_element| drag_element.id == item.id) { continue; }
+
+                let icon_size = item.size.expect("Placed icons should have a size") * map_scale;
+                let rect = egui::Rect::from_center_size(self.view.map_to_screen(&screen, item.pos), icon_size);
+                
+                let widget = egui::Image::new(kind_to_source(item.kind))
+                    .sense(egui::Sense::click_and_drag());
 
-            let mut x = x_start;
-            let mut y = y_start;
-            for row in &mut self.row_icons {
-                for item in row {
+                let mut response = map_ui.put(rect, widget);
+                // Linked icons cover their entrance marker, so they show its details instead
+                if let Some(entrance) = item.entrance.and_then(|id| self.entrances.get(&id)) {
+                    response = response.on_hover_text(self.marker_tooltip(entrance));
+                }
+                if response.secondary_clicked() {
+                    edits.push(Edit::RemovePlacedIcon(item.clone()));
+                }
+
+                if response.drag_started() && kind_is_dragable(item.kind) {
+                    self.drag_element = Some(item.clone());
+                }
+            }
+
+            // Drawn over placed icons, which cover the entrances they are linked to
+            let hits = self.search_hits();
+            if !hits.is_empty() {
+                let pulse = ((ui.input(|i| i.time) * 4.0).sin() * 0.5 + 0.5) as f32;
+                let radius = (10.0 + 6.0 * pulse) * map_scale;
+                for hit in hits {
+                    let center = self.view.map_to_screen(&screen, hit.pos);
+                    map_ui.painter().circle_stroke(center, radius, egui::Stroke::new(3.0, Color32::from_rgb(0, 200, 255)));
+                }
+                ui.ctx().request_repaint();
+            }
+
+            if self.settings.show_link_position {
+                self.draw_link_marker(&map_ui, &screen);
+            }
+
+            let offset = layout::ROW_OFFSET * screen.scale;
+            let icon_size = egui::Vec2::splat(layout::ROW_ICON_SIZE * screen.scale);
+
+            let placed_kinds: HashSet<MapElementKind> = self.placed_icons.values().map(|icon| icon.kind).collect();
+            let mut x = screen.rows_origin.x;
+            let mut y = screen.rows_origin.y;
+            for (row_index, row) in self.row_icons.iter().enumerate() {
+                for (column, item) in row.iter().enumerate() {
                     let item_pos = egui::Pos2::new(x, y);
                     x += offset;
 
                     if item.kind == MapElementKind::BLANK { continue; }
 
                     let rect = egui::Rect::from_center_size(item_pos, icon_size);
-                    let widget = egui::Image::new(kind_to_source(item.kind))
+                    let source = if item.kind == MapElementKind::GLOVE && item.level >= 2 {
+                        egui::include_image!("../assets/icons/items/mitt.png")
+                    } else {
+                        kind_to_source(item.kind)
+                    };
+                    let widget = egui::Image::new(source)
                         .sense(egui::Sense::click_and_drag());
 
                     let response = ui.put(rect, widget);
+                    let mut new_state = None;
                     if response.secondary_clicked() {
-                        item.state = RowElementState::CROSSED;
+                        new_state = Some(RowElementState::Crossed);
                     }
                     if response.middle_clicked() {
-                        item.state = RowElementState::CIRCLED;
+                        new_state = Some(RowElementState::Circled);
+                    }
+                    if let Some(state) = new_state.filter(|state| *state != item.state || !item.manual_override) {
+                        // A state picked by hand is kept, the autotracker no longer changes it
+                        let to = RowElement { state, manual_override: true, ..item.clone() };
+                        edits.push(Edit::SetRowElement { row: row_index, column, from: item.clone(), to });
                     }
 
                     if response.hovered() {
                         let category = kind_to_category(&item.kind);
-                        for (_k,v) in self.placed_icons.iter().filter(|&(_k,v)| category == kind_to_category(&v.kind)) {
-                            ui.painter().line_segment([item_pos, v.pos], egui::Stroke::new(5.0, Color32::RED));
+                        for v in self.placed_icons.values().filter(|v| category == kind_to_category(&v.kind)) {
+                            let target = self.view.map_to_screen(&screen, v.pos);
+                            ui.painter().line_segment([item_pos, target], egui::Stroke::new(5.0, Color32::RED));
                         }
                     }
 
-                    if item.state == RowElementState::CROSSED {
+                    let state = shown_state(item, &placed_kinds);
+                    if state == RowElementState::Crossed {
                         ui.put(rect, egui::Image::new(egui::include_image!("../assets/icons/cross.png")));
-                    } else if item.state == RowElementState::CIRCLED {
+                    } else if state == RowElementState::Circled {
                         ui.put(rect, egui::Image::new(egui::include_image!("../assets/icons/todo.png")));
+                    } else if state == RowElementState::Owned {
+                        let stroke = egui::Stroke::new(3.0 * screen.scale, Color32::from_rgb(80, 200, 80));
+                        ui.painter().rect_stroke(rect, 6.0 * screen.scale, stroke);
+                        // The mitt has its own icon, other upgrades get their level in the corner
+                        if item.level >= 2 && item.kind != MapElementKind::GLOVE {
+                            ui.painter().text(
+                                rect.right_bottom(),
+                                egui::Align2::RIGHT_BOTTOM,
+                                item.level.to_string(),
+                                egui::FontId::proportional(16.0 * screen.scale),
+                                Color32::WHITE
+                            );
+                        }
                     }
 
                     if response.drag_started() {
                         let mut new_drag = map_element_kind_to_map_element(item.kind);
-                        new_drag.size = Some(Vec2::new(25.0, 25.0));
+                        new_drag.size = Some(Vec2::new(PLACED_ICON_SIZE, PLACED_ICON_SIZE));
                         
                         self.drag_element = Some(new_drag);
                     }
-                    else if response.drag_released() {
-                        let new_item = MapElement {
-                            pos: ui.input(|i| i.pointer.latest_pos().expect("Expected a pointer while dragging")),
-                            size: self.drag_element.expect("Drag Element should not be null").size,
-                            kind: self.drag_element.expect("Drag Element should not be null").kind,
-                            id: Uuid::new_v4(),
-                            checked: false
-                        };
-
-                        self.placed_icons.insert(new_item.id, new_item);
-                        self.drag_element = None;
-                    }
                 }
 
-                x = x_start;
+                x = screen.rows_origin.x;
                 y += offset;
             }
 
-            let mut drag_started_item: Option<MapElement> = None;
-            let mut drag_released_item: Option<MapElement> = None;
-            let mut disabled_item: Option<MapElement> = None;
-            for (_id, item) in &mut self.entrances {
-                let icon_size = egui::Vec2::new(15.0, 15.0);
-                let rect = egui::Rect::from_center_size(item.pos, icon_size);
-                
-                let widget = egui::Image::new(kind_to_source(item.kind))
-                    .sense(egui::Sense::click());
-                    // .tint(Color32::from_white_alpha(if item.1.checked { 25 } else { 255 } ));
+            if let Some(drag_element) = self.drag_element.clone() {
+                let pointer = ui.input(|i| i.pointer.latest_pos());
+                if ui.input(|i| i.pointer.any_released()) {
+                    // Dropping anywhere but the maps cancels the drag
+                    if let Some(pos) = pointer.filter(|pos| screen.map_rect.contains(*pos)) {
+                        let pos = self.view.screen_to_map(&screen, pos);
 
-                let response = ui.put(rect, widget);
-                if response.secondary_clicked() {
-                    disabled_item = Some(*item);
+                        edits.push(self.place_icon(drag_element, pos));
+                    }
+                    self.drag_element = None;
+                } else if let Some(pos) = pointer {
+                    let size = drag_element.size.expect("Drag Element should have a size") * map_scale;
+                    let rect = egui::Rect::from_center_size(pos, size);
+                    ui.put(rect, egui::Image::new(kind_to_source(drag_element.kind)));
                 }
             }
 
-            for (_id, item) in &mut self.placed_icons {
-                let icon_size = item.size.expect("Placed icons should have a size");
-                let rect = egui::Rect::from_center_size(item.pos, icon_size);
-                
-                let widget = egui::Image::new(kind_to_source(item.kind))
-                    .sense(egui::Sense::click_and_drag());
+            for edit in edits {
+                self.perform(edit);
+            }
 
-                let response = ui.put(rect, widget);
-                if response.secondary_clicked() {
-                    disabled_item = Some(*item);
-                }
+            self.controls(ui, screen.controls_rect);
+        });
 
-                if response.drag_started() && kind_is_dragable(item.kind) {
-                    self.drag_element = Some(*item);
-                    drag_started_item = self.drag_element;
-                }
-                else if response.drag_released() && self.drag_element.is_some() {
-                    drag_released_item = Some(MapElement {
-                        pos: ui.input(|i| i.pointer.latest_pos().expect("Expected a pointer while dragging")),
-                        size: self.drag_element.expect("Drag Element should not be null").size,
-                        kind: self.drag_element.expect("Drag Element should not be null").kind,
-                        id: Uuid::new_v4(),
-                        checked: false
-                    });
-                    self.drag_element = None;
-                }
-            }
+        self.show_error_dialog(ctx);
 
-            match drag_started_item {
-                Some(drag_item) => self.placed_icons.remove(&drag_item.id),
-                None => None
-            };
-            match drag_released_item {
-                Some(drag_item) => self.placed_icons.insert(drag_item.id, drag_item),
-                None => None
-            };
-            match disabled_item {
-                Some(disable_item) => {
-                    self.entrances.remove(&disable_item.id);
-                    self.placed_icons.remove(&disable_item.id);
-                }
-                None => ()
-            };
+        self.autosave();
+    }
 
-            match self.drag_element {
-                Some(drag_element) => {
-                    let mouse_position = ui.input(|i| i.pointer.latest_pos().expect("Expected a pointer while dragging"));
-                    let rect = egui::Rect::from_center_size(mouse_position, drag_element.size.expect("Drag Element should have a size"));
-                    ui.put(rect, egui::Image::new(kind_to_source(drag_element.kind)));
-                }
-                None => ()
-            }
-        });
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.dirty = true;
+        self.autosave();
+        settings::save(&self.settings);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(name: &str, x: f32) -> MapElement {
+        MapElement {
+            pos: egui::Pos2::new(x, 10.0),
+            name: Some(String::from(name)),
+            ..map_element_kind_to_map_element(MapElementKind::ENTRANCE)
+        }
+    }
+
+    fn by_id(items: &[&MapElement]) -> HashMap<Uuid, MapElement> {
+        items.iter().map(|item| (item.id, (*item).clone())).collect()
+    }
+
+    #[test]
+    fn search_matches_names_checks_and_linked_destinations() {
+        let spike = marker("Spike Cave", 300.0);
+        let house = marker("Link's House", 100.0);
+        let checked = MapElement { check: Some(MapElementKind::HYPE), ..marker("Hype Cave", 200.0) };
+        let entrances = by_id(&[&spike, &house, &checked]);
+        let linked = MapElement { entrance: Some(house.id), ..map_element_kind_to_map_element(MapElementKind::SPIKE) };
+        let placed_icons = by_id(&[&linked]);
+
+        let names = |search: &str| search_hits(search, &entrances, &placed_icons).iter()
+            .map(|item| item.name.clone().unwrap_or_default())
+            .collect::<Vec<String>>();
+        assert_eq!(names("  SPIKE "), vec!["Link's House", "Spike Cave"]);
+        assert_eq!(names("hype"), vec!["Hype Cave"]);
+        assert_eq!(names("cave"), vec!["Link's House", "Hype Cave", "Spike Cave"]);
+        assert!(names("   ").is_empty());
+        assert!(names("lumberjacks").is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// The connectors the tracker knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemorySourceKind {
+    Usb2snes,
+    RetroArch,
+    SrmFile
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceError {
+    /// Could not reach the source at all.
+    Connect(String),
+    /// The connection went away mid-session.
+    Closed,
+    /// The source stopped answering.
+    Timeout,
+    /// The source answered with something unexpected.
+    Protocol(String)
+}
+
+impl std::fmt::Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceError::Connect(err) => write!(f, "Unable to connect: {}", err),
+            SourceError::Closed => write!(f, "Connection lost"),
+            SourceError::Timeout => write!(f, "No answer in time"),
+            SourceError::Protocol(err) => write!(f, "Unexpected answer: {}", err)
+        }
+    }
+}
+
+/// Somewhere console memory can be read from, such as a usb2snes server or an emulator.
+///
+/// Offsets are relative to the start of WRAM ($7E0000), each source maps them to its own address space.
+/// After any error the autotracker calls `disconnect` and starts over with `connect`.
+#[async_trait]
+pub trait MemorySource: Send {
+    async fn connect(&mut self) -> Result<(), SourceError>;
+
+    /// What can be attached to. Sources without a choice return a single entry.
+    async fn devices(&mut self) -> Result<Vec<String>, SourceError>;
+
+    async fn attach(&mut self, device: &str) -> Result<(), SourceError>;
+
+    async fn read_wram(&mut self, offset: u32, size: usize) -> Result<Vec<u8>, SourceError>;
+
+    /// Whether the game mode can be read. Sources that only see saved data skip the game mode checks.
+    fn has_game_state(&self) -> bool {
+        true
+    }
+
+    fn disconnect(&mut self);
+}
@@ -0,0 +1,237 @@
+//! In-process usb2snes server for exercising the autotracker without hardware.
+//!
+//! The server answers DeviceList, Attach and GetAddress from a scripted list of
+//! memory snapshots. Tests move through the script with `advance`.
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::autotracker::{GAMEMODE_OFFSET, SAVEDATA_OFFSET, SAVEDATA_SIZE};
+use crate::game_state::GAME_STATE_SIZE;
+use crate::usb2snes::WRAM_START;
+
+const GAMEMODE_ADDRESS: u32 = WRAM_START + GAMEMODE_OFFSET;
+const SAVEDATA_START: u32 = WRAM_START + SAVEDATA_OFFSET;
+
+/// Overworld and underworld, modes the autotracker trusts.
+pub const OVERWORLD_GAMEMODE: u8 = 0x09;
+pub const DUNGEON_GAMEMODE: u8 = 0x07;
+/// Real devices split larger reads into messages of this size.
+const CHUNK_SIZE: usize = 1024;
+
+/// Console memory as the autotracker sees it at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub gamemode: u8,
+    pub savedata: Vec<u8>,
+    /// Start of WRAM, where Link's position lives.
+    pub game_state: Vec<u8>
+}
+
+impl Snapshot {
+    pub fn new(gamemode: u8, savedata: Vec<u8>) -> Snapshot {
+        Snapshot { gamemode, savedata, game_state: vec![0; GAME_STATE_SIZE] }
+    }
+
+    /// Link standing on an overworld screen at overworld pixel coordinates.
+    pub fn outside(mut self, screen: u8, x: u16, y: u16) -> Snapshot {
+        self.game_state[0x1B] = 0;
+        self.game_state[0x8A] = screen;
+        self.game_state[0x20..0x22].copy_from_slice(&y.to_le_bytes());
+        self.game_state[0x22..0x24].copy_from_slice(&x.to_le_bytes());
+        self
+    }
+
+    /// Link in an underworld room, having come in through `entrance`.
+    pub fn inside(mut self, room: u16, entrance: u8) -> Snapshot {
+        self.game_state[0x1B] = 1;
+        self.game_state[0xA0..0xA2].copy_from_slice(&room.to_le_bytes());
+        self.game_state[0x10E] = entrance;
+        self
+    }
+
+    /// Empty save data in the given game mode.
+    pub fn blank(gamemode: u8) -> Snapshot {
+        Snapshot::new(gamemode, vec![0; SAVEDATA_SIZE])
+    }
+
+    /// Save data region stored in a file, served as if on the overworld.
+    pub fn from_file(path: &Path) -> io::Result<Snapshot> {
+        let savedata = std::fs::read(path)?;
+        if savedata.len() != SAVEDATA_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Snapshot has {} bytes, expected {}", savedata.len(), SAVEDATA_SIZE)
+            ));
+        }
+
+        Ok(Snapshot::new(OVERWORLD_GAMEMODE, savedata))
+    }
+
+    fn read(&self, address: u32) -> u8 {
+        if address == GAMEMODE_ADDRESS {
+            return self.gamemode;
+        }
+        if let Some(value) = address.checked_sub(WRAM_START).and_then(|offset| self.game_state.get(offset as usize)) {
+            return *value;
+        }
+
+        let offset = address.wrapping_sub(SAVEDATA_START) as usize;
+        self.savedata.get(offset).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MockRequest {
+    opcode: String,
+    #[serde(default)]
+    operands: Vec<String>
+}
+
+struct MockState {
+    devices: Vec<String>,
+    script: Vec<Snapshot>,
+    position: usize,
+    attached: Option<String>,
+    gamemode_reads: usize
+}
+
+/// A running mock server. Dropping it stops the server and closes every connection.
+pub struct MockUsb2Snes {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+    kick: Arc<Notify>,
+    runtime: Option<tokio::runtime::Runtime>
+}
+
+impl MockUsb2Snes {
+    /// Listens on a free local port. The first snapshot of `script` is served until `advance` is called.
+    pub fn start(devices: &[&str], script: Vec<Snapshot>) -> MockUsb2Snes {
+        assert!(!script.is_empty(), "Mock script needs at least one snapshot");
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("Unable to start mock runtime");
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).expect("Unable to bind mock server");
+        let url = format!("ws://{}", listener.local_addr().expect("Mock server has no address"));
+
+        let state = Arc::new(Mutex::new(MockState {
+            devices: devices.iter().map(|device| device.to_string()).collect(),
+            script,
+            position: 0,
+            attached: None,
+            gamemode_reads: 0
+        }));
+        let kick = Arc::new(Notify::new());
+
+        let server_state = state.clone();
+        let server_kick = kick.clone();
+        runtime.spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, server_state.clone(), server_kick.clone()));
+            }
+        });
+
+        MockUsb2Snes { url, state, kick, runtime: Some(runtime) }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Moves on to the next scripted snapshot. Returns false once the script is exhausted.
+    pub fn advance(&self) -> bool {
+        let mut state = self.state.lock().expect("Mock state poisoned");
+        if state.position + 1 >= state.script.len() {
+            return false;
+        }
+
+        state.position += 1;
+        true
+    }
+
+    pub fn attached(&self) -> Option<String> {
+        self.state.lock().expect("Mock state poisoned").attached.clone()
+    }
+
+    /// How often the game mode has been read, a stand-in for completed polls.
+    pub fn gamemode_reads(&self) -> usize {
+        self.state.lock().expect("Mock state poisoned").gamemode_reads
+    }
+
+    /// Drops every open connection, like a device being unplugged.
+    pub fn disconnect_all(&self) {
+        self.kick.notify_waiters();
+    }
+}
+
+impl Drop for MockUsb2Snes {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+async fn serve(stream: tokio::net::TcpStream, state: Arc<Mutex<MockState>>, kick: Arc<Notify>) {
+    let Ok(mut socket) = tokio_tungstenite::accept_async(stream).await else { return; };
+
+    loop {
+        let message = tokio::select! {
+            message = socket.next() => message,
+            _ = kick.notified() => return
+        };
+        let Some(Ok(Message::Text(text))) = message else {
+            match message {
+                Some(Ok(_)) => continue,
+                _ => return
+            }
+        };
+        let Ok(request) = serde_json::from_str::<MockRequest>(&text) else { return; };
+
+        let replies = respond(&state, request);
+        for reply in replies {
+            if socket.send(reply).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn respond(state: &Mutex<MockState>, request: MockRequest) -> Vec<Message> {
+    let mut state = state.lock().expect("Mock state poisoned");
+    match request.opcode.as_str() {
+        "DeviceList" => {
+            let reply = serde_json::json!({ "Results": state.devices });
+            vec![Message::Text(reply.to_string())]
+        }
+        "Attach" => {
+            state.attached = request.operands.first().cloned();
+            Vec::new()
+        }
+        "GetAddress" if state.attached.is_some() => {
+            let snapshot = state.script[state.position].clone();
+            let mut data: Vec<u8> = Vec::new();
+            for pair in request.operands.chunks(2) {
+                let address = u32::from_str_radix(&pair[0], 16).expect("Address should be hex");
+                let size = usize::from_str_radix(&pair[1], 16).expect("Size should be hex");
+                if address == GAMEMODE_ADDRESS {
+                    state.gamemode_reads += 1;
+                }
+                data.extend((0..size as u32).map(|offset| snapshot.read(address + offset)));
+            }
+
+            data.chunks(CHUNK_SIZE).map(|chunk| Message::Binary(chunk.to_vec())).collect()
+        }
+        _ => Vec::new()
+    }
+}
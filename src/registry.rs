@@ -0,0 +1,239 @@
+use eframe::egui::{self, ImageSource};
+use serde::{Deserialize, Serialize};
+use serde::de::IntoDeserializer;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::map_element;
+use map_element::{MapElementCategory, MapElementKind};
+
+/// Kind manifest shipped with the tracker. A custom manifest only needs the entries it changes,
+/// new kinds still need a `MapElementKind` variant.
+pub const BUILTIN_MANIFEST: &str = include_str!("../assets/kinds.json");
+pub const MANIFEST_FILE: &str = "kinds.json";
+
+static REGISTRY: OnceLock<KindRegistry> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KindInfo {
+    pub id: String,
+    pub name: String,
+    /// Relative to the assets directory for built-in icons, otherwise to the manifest file.
+    pub icon: String,
+    pub category: MapElementCategory,
+    #[serde(default)]
+    pub draggable: bool,
+    /// Only one icon of a unique kind can be placed on the map at a time.
+    #[serde(default)]
+    pub unique: bool
+}
+
+#[derive(Deserialize)]
+struct ConnectorJson {
+    name: String,
+    members: Vec<String>
+}
+
+#[derive(Deserialize)]
+struct ManifestJson {
+    kinds: Vec<KindInfo>,
+    /// Replaces the built-in connectors when given.
+    #[serde(default)]
+    connectors: Option<Vec<ConnectorJson>>
+}
+
+/// One cave or dungeon with several ends, such as the three doors of Paradox Cave.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connector {
+    pub name: String,
+    /// In the order the ends are joined up, e.g. upper, middle and lower.
+    pub members: Vec<MapElementKind>
+}
+
+pub struct KindRegistry {
+    kinds: HashMap<MapElementKind, KindInfo>,
+    connectors: Vec<Connector>,
+    sources: HashMap<MapElementKind, ImageSource<'static>>,
+    fallback: KindInfo
+}
+
+fn parse_kind(id: &str) -> Option<MapElementKind> {
+    let deserializer: serde::de::value::StrDeserializer<serde::de::value::Error> = id.into_deserializer();
+    MapElementKind::deserialize(deserializer).ok()
+}
+
+// `builtin_icon`, generated by build.rs from the icons named in assets/kinds.json
+include!(concat!(env!("OUT_DIR"), "/builtin_icons.rs"));
+
+/// Built-in icons by path under `assets`, anything else must exist next to the custom manifest.
+fn icon_source(info: &KindInfo, base_dir: Option<&Path>) -> Result<ImageSource<'static>, String> {
+    if let Some(source) = builtin_icon(&info.icon) {
+        return Ok(source);
+    }
+
+    let path = base_dir.unwrap_or(Path::new("")).join(&info.icon);
+    let path = fs::canonicalize(&path)
+        .map_err(|err| format!("Icon {} of kind {} is not usable: {}", path.display(), info.id, err))?;
+    Ok(ImageSource::Uri(format!("file://{}", path.display()).into()))
+}
+
+impl KindRegistry {
+    fn from_manifests(custom: Option<(&str, &Path)>) -> Result<KindRegistry, String> {
+        let mut registry = KindRegistry {
+            kinds: HashMap::new(),
+            connectors: Vec::new(),
+            sources: HashMap::new(),
+            fallback: KindInfo {
+                id: String::from("BLANK"),
+                name: String::new(),
+                icon: String::from("icons/blank.png"),
+                category: MapElementCategory::BLANK,
+                draggable: false,
+                unique: false
+            }
+        };
+
+        let builtin: ManifestJson = serde_json::from_str(BUILTIN_MANIFEST).expect("Built-in kind manifest should be valid");
+        registry.add(builtin, None)?;
+
+        if let Some((json_data, path)) = custom {
+            let manifest: ManifestJson = serde_json::from_str(json_data)
+                .map_err(|err| format!("Kind manifest {} is not valid: {}", path.display(), err))?;
+            registry.add(manifest, path.parent())?;
+        }
+
+        Ok(registry)
+    }
+
+    fn add(&mut self, manifest: ManifestJson, base_dir: Option<&Path>) -> Result<(), String> {
+        for info in manifest.kinds {
+            let kind = parse_kind(&info.id).ok_or_else(|| format!(
+                "Kind manifest has unknown id \"{}\", a manifest can only change the kinds the tracker knows",
+                info.id
+            ))?;
+            self.sources.insert(kind, icon_source(&info, base_dir)?);
+            self.kinds.insert(kind, info);
+        }
+
+        if let Some(connectors) = manifest.connectors {
+            self.connectors = connectors.into_iter().map(|connector| {
+                let members = connector.members.iter()
+                    .map(|id| self.kind_from_id(id).ok_or_else(|| format!("Connector {} has unknown member \"{}\"", connector.name, id)))
+                    .collect::<Result<Vec<MapElementKind>, String>>()?;
+                Ok(Connector { name: connector.name, members })
+            }).collect::<Result<Vec<Connector>, String>>()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn info(&self, kind: MapElementKind) -> &KindInfo {
+        self.kinds.get(&kind).unwrap_or(&self.fallback)
+    }
+
+    pub fn source(&self, kind: MapElementKind) -> ImageSource<'static> {
+        self.sources.get(&kind).cloned()
+            .unwrap_or_else(|| builtin_icon(&self.fallback.icon).expect("Blank icon should be built in"))
+    }
+
+    pub fn connectors(&self) -> &[Connector] {
+        &self.connectors
+    }
+
+    /// The connector a kind is one end of.
+    pub fn connector(&self, kind: MapElementKind) -> Option<&Connector> {
+        self.connectors.iter().find(|connector| connector.members.contains(&kind))
+    }
+
+    pub fn kind_from_id(&self, id: &str) -> Option<MapElementKind> {
+        parse_kind(id).filter(|kind| self.kinds.contains_key(kind))
+    }
+}
+
+/// Sets up the registry from the built-in manifest plus an optional custom one.
+/// A broken custom manifest is reported and the built-in one is used on its own.
+pub fn init(custom_path: Option<&Path>) -> Result<(), String> {
+    let result = match custom_path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read kind manifest {}: {}", path.display(), err))
+            .and_then(|json_data| KindRegistry::from_manifests(Some((&json_data, path)))),
+        None => KindRegistry::from_manifests(None)
+    };
+
+    let (registry, error) = match result {
+        Ok(registry) => (registry, None),
+        Err(err) => (KindRegistry::from_manifests(None).expect("Built-in kind manifest should be valid"), Some(err))
+    };
+
+    let _ = REGISTRY.set(registry);
+    match error {
+        Some(err) => Err(err),
+        None => Ok(())
+    }
+}
+
+pub fn registry() -> &'static KindRegistry {
+    REGISTRY.get_or_init(|| KindRegistry::from_manifests(None).expect("Built-in kind manifest should be valid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::ENTRANCE_DESTINATIONS;
+
+    fn custom_manifest(json_data: &str) -> Result<KindRegistry, String> {
+        KindRegistry::from_manifests(Some((json_data, Path::new("custom/kinds.json"))))
+    }
+
+    #[test]
+    fn builtin_manifest_has_an_icon_for_every_kind() {
+        let registry = KindRegistry::from_manifests(None).expect("Built-in kind manifest should be valid");
+        for kind in registry.kinds.keys() {
+            assert!(builtin_icon(&registry.info(*kind).icon).is_some(), "{:?} has no built-in icon", kind);
+        }
+    }
+
+    #[test]
+    fn rejects_kinds_the_tracker_does_not_know() {
+        let err = custom_manifest(r#"{"kinds": [{"id": "LUMBERJACKS", "name": "Lumberjacks", "icon": "icons/blank.png", "category": "BLANK"}]}"#)
+            .err().expect("Unknown kinds should be rejected");
+        assert!(err.contains("LUMBERJACKS"));
+    }
+
+    #[test]
+    fn reports_missing_icons() {
+        let err = custom_manifest(r#"{"kinds": [{"id": "SPIKE", "name": "Spike Cave", "icon": "spike-custom.png", "category": "SPIKE"}]}"#)
+            .err().expect("Missing icons should be reported");
+        assert!(err.contains("spike-custom.png"));
+
+        let registry = custom_manifest(r#"{"kinds": [{"id": "SPIKE", "name": "Spike", "icon": "icons/blank.png", "category": "SPIKE"}]}"#)
+            .expect("Built-in icons need no file next to the manifest");
+        assert_eq!(registry.info(MapElementKind::SPIKE).name, "Spike");
+    }
+
+    #[test]
+    fn connectors_are_listed_explicitly() {
+        let registry = KindRegistry::from_manifests(None).expect("Built-in kind manifest should be valid");
+        let paradox = registry.connector(MapElementKind::PARAM).expect("Paradox Cave is a connector");
+        assert_eq!(paradox.members, vec![MapElementKind::PARAU, MapElementKind::PARAM, MapElementKind::PARAL]);
+        // Three separate caves that only share a category
+        assert_eq!(registry.connector(MapElementKind::RESCUE), None);
+
+        let err = custom_manifest(r#"{"kinds": [], "connectors": [{"name": "Lumberjacks", "members": ["LUMBERJACKS"]}]}"#)
+            .err().expect("Unknown members should be rejected");
+        assert!(err.contains("LUMBERJACKS"));
+    }
+
+    #[test]
+    fn kinds_with_several_doors_are_not_unique() {
+        let registry = KindRegistry::from_manifests(None).expect("Built-in kind manifest should be valid");
+        for (entrance, kind) in ENTRANCE_DESTINATIONS {
+            let doors = ENTRANCE_DESTINATIONS.iter().filter(|(_, other)| other == kind).count();
+            if doors > 1 {
+                assert!(!registry.info(*kind).unique, "{:?} has {} doors (entrance {:#04x}) but is unique", kind, doors, entrance);
+            }
+        }
+    }
+}
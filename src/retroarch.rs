@@ -0,0 +1,221 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+use crate::memory_source::{MemorySource, SourceError};
+
+/// RetroArch's default network command port.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:55355";
+/// Where WRAM starts on the SNES bus, which is what READ_CORE_MEMORY addresses.
+pub const WRAM_START: u32 = 0x7E0000;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+/// Replies are hex text, so larger reads are split to keep each datagram small.
+const MAX_READ_SIZE: usize = 0x200;
+const MAX_REPLY_SIZE: usize = 8192;
+
+/// RetroArch with network commands enabled, read over UDP with READ_CORE_MEMORY.
+pub struct RetroArchSource {
+    address: String,
+    socket: Option<UdpSocket>
+}
+
+impl RetroArchSource {
+    pub fn new(address: &str) -> RetroArchSource {
+        RetroArchSource { address: address.to_string(), socket: None }
+    }
+
+    async fn command(&self, command: &str) -> Result<(), SourceError> {
+        let socket = self.socket.as_ref().ok_or(SourceError::Closed)?;
+        socket.send(format!("{}\n", command).as_bytes()).await.map_err(|_| SourceError::Closed)?;
+        Ok(())
+    }
+
+    async fn reply(&self) -> Result<String, SourceError> {
+        let socket = self.socket.as_ref().ok_or(SourceError::Closed)?;
+        let mut buffer = vec![0; MAX_REPLY_SIZE];
+        let size = match tokio::time::timeout(READ_TIMEOUT, socket.recv(&mut buffer)).await {
+            Ok(Ok(size)) => size,
+            // Nothing listening on the port shows up as a refused connection on some systems
+            Ok(Err(_)) => return Err(SourceError::Closed),
+            Err(_) => return Err(SourceError::Timeout)
+        };
+
+        Ok(String::from_utf8_lossy(&buffer[..size]).trim().to_string())
+    }
+
+    async fn read_chunk(&self, address: u32, size: usize) -> Result<Vec<u8>, SourceError> {
+        self.command(&format!("READ_CORE_MEMORY {:x} {}", address, size)).await?;
+        loop {
+            let reply = self.reply().await?;
+            // Late answers to reads that timed out earlier are skipped
+            if let Some(data) = parse_read_reply(&reply, address)? {
+                if data.len() != size {
+                    return Err(SourceError::Protocol(format!("asked for {} bytes, got {}", size, data.len())));
+                }
+                return Ok(data);
+            }
+        }
+    }
+}
+
+/// The bytes of a READ_CORE_MEMORY reply for `address`, or None if the reply is for something else.
+fn parse_read_reply(reply: &str, address: u32) -> Result<Option<Vec<u8>>, SourceError> {
+    let mut parts = reply.split_whitespace();
+    if parts.next() != Some("READ_CORE_MEMORY") {
+        return Ok(None);
+    }
+    if parts.next().and_then(|part| u32::from_str_radix(part, 16).ok()) != Some(address) {
+        return Ok(None);
+    }
+
+    let parts: Vec<&str> = parts.collect();
+    if parts.first() == Some(&"-1") {
+        return Err(SourceError::Protocol(parts[1..].join(" ")));
+    }
+
+    let data = parts.iter()
+        .map(|byte| u8::from_str_radix(byte, 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|err| SourceError::Protocol(err.to_string()))?;
+    Ok(Some(data))
+}
+
+#[async_trait]
+impl MemorySource for RetroArchSource {
+    async fn connect(&mut self) -> Result<(), SourceError> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await.map_err(|err| SourceError::Connect(err.to_string()))?;
+        socket.connect(&self.address).await.map_err(|err| SourceError::Connect(err.to_string()))?;
+        self.socket = Some(socket);
+
+        // UDP has no connection, so only an answer shows that RetroArch is listening
+        self.command("VERSION").await?;
+        match self.reply().await {
+            Ok(_) => Ok(()),
+            Err(SourceError::Timeout) | Err(SourceError::Closed) => Err(SourceError::Connect(format!(
+                "RetroArch is not answering on {}, are network commands enabled?",
+                self.address
+            ))),
+            Err(err) => Err(err)
+        }
+    }
+
+    async fn devices(&mut self) -> Result<Vec<String>, SourceError> {
+        Ok(vec![format!("RetroArch ({})", self.address)])
+    }
+
+    async fn attach(&mut self, _device: &str) -> Result<(), SourceError> {
+        Ok(())
+    }
+
+    async fn read_wram(&mut self, offset: u32, size: usize) -> Result<Vec<u8>, SourceError> {
+        let mut data: Vec<u8> = Vec::with_capacity(size);
+        while data.len() < size {
+            let chunk_size = (size - data.len()).min(MAX_READ_SIZE);
+            let chunk = self.read_chunk(WRAM_START + offset + data.len() as u32, chunk_size).await?;
+            data.extend(chunk);
+        }
+
+        Ok(data)
+    }
+
+    fn disconnect(&mut self) {
+        self.socket = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autotracker::{Autotracker, AutotrackerStatus, GAMEMODE_OFFSET, SAVEDATA_OFFSET};
+    use crate::autotracker::test_helpers::{wait_for_checks, wait_for_status};
+    use crate::map_element::MapElementKind;
+    use crate::sram::CheckStatus;
+
+    const OVERWORLD_GAMEMODE: u8 = 0x09;
+
+    /// Answers VERSION and READ_CORE_MEMORY like RetroArch would, from a fixed copy of WRAM.
+    fn start_responder(wram: Vec<u8>) -> (String, tokio::runtime::Runtime) {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("Unable to start responder runtime");
+        let socket = runtime.block_on(UdpSocket::bind("127.0.0.1:0")).expect("Unable to bind responder");
+        let address = socket.local_addr().expect("Responder has no address").to_string();
+
+        runtime.spawn(async move {
+            let mut buffer = vec![0; MAX_REPLY_SIZE];
+            while let Ok((size, peer)) = socket.recv_from(&mut buffer).await {
+                let command = String::from_utf8_lossy(&buffer[..size]).to_string();
+                let parts: Vec<&str> = command.split_whitespace().collect();
+                let reply = match parts.as_slice() {
+                    ["VERSION"] => String::from("1.17.0"),
+                    ["READ_CORE_MEMORY", address, size] => {
+                        let start = u32::from_str_radix(address, 16).unwrap();
+                        let size: usize = size.parse().unwrap();
+                        match start.checked_sub(WRAM_START) {
+                            Some(offset) if offset as usize + size <= wram.len() => {
+                                let bytes: Vec<String> = wram[offset as usize..offset as usize + size].iter()
+                                    .map(|byte| format!("{:02x}", byte))
+                                    .collect();
+                                format!("READ_CORE_MEMORY {} {}", address, bytes.join(" "))
+                            }
+                            _ => format!("READ_CORE_MEMORY {} -1 no memory map defined", address)
+                        }
+                    }
+                    _ => continue
+                };
+                let _ = socket.send_to(reply.as_bytes(), peer).await;
+            }
+        });
+
+        (address, runtime)
+    }
+
+    #[test]
+    fn parses_read_replies() {
+        assert_eq!(parse_read_reply("READ_CORE_MEMORY 7e0010 09", 0x7E0010), Ok(Some(vec![0x09])));
+        assert_eq!(parse_read_reply("READ_CORE_MEMORY 7e0011 09", 0x7E0010), Ok(None));
+        assert_eq!(parse_read_reply("1.17.0", 0x7E0010), Ok(None));
+        assert_eq!(
+            parse_read_reply("READ_CORE_MEMORY 7e0010 -1 no memory map defined", 0x7E0010),
+            Err(SourceError::Protocol(String::from("no memory map defined")))
+        );
+    }
+
+    #[test]
+    fn tracks_checks_through_retroarch() {
+        let mut wram = vec![0; 0x10000];
+        wram[GAMEMODE_OFFSET as usize] = OVERWORLD_GAMEMODE;
+        wram[SAVEDATA_OFFSET as usize + 0x078] = 0xF0;
+        wram[SAVEDATA_OFFSET as usize + 0x410] = 0x80;
+        let (address, _responder) = start_responder(wram);
+
+        let source = Box::new(RetroArchSource::new(&address));
+        let mut tracker = Autotracker::start(source, Duration::from_millis(20), None, || ()).expect("Autotracker should start");
+
+        let changes = wait_for_checks(&mut tracker);
+
+        let completed: Vec<MapElementKind> = changes.iter()
+            .filter(|change| change.new == CheckStatus::Complete)
+            .map(|change| change.kind)
+            .collect();
+        assert_eq!(completed, vec![MapElementKind::HOOKSHOT, MapElementKind::LIBRARY]);
+        assert_eq!(tracker.info().status, AutotrackerStatus::Tracking);
+        assert_eq!(tracker.info().device, Some(format!("RetroArch ({})", address)));
+    }
+
+    #[test]
+    fn reports_when_retroarch_is_not_running() {
+        // Bound but never answering, like a port nothing is listening on
+        let silent = std::net::UdpSocket::bind("127.0.0.1:0").expect("Unable to bind");
+        let address = silent.local_addr().unwrap().to_string();
+
+        let source = Box::new(RetroArchSource::new(&address));
+        let mut tracker = Autotracker::start(source, Duration::from_millis(20), None, || ()).expect("Autotracker should start");
+
+        wait_for_status(&mut tracker, AutotrackerStatus::Reconnecting);
+        assert!(tracker.info().error.as_deref().unwrap_or_default().contains("not answering"));
+    }
+}
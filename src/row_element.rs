@@ -1,28 +1,104 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
 use crate::map_element;
 use map_element::*;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::registry::registry;
+use crate::sram::CheckStatus;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RowElementState {
-    NONE,
-    CROSSED,
-    CIRCLED
+    None,
+    Crossed,
+    Circled,
+    /// An inventory item the player has, set by the autotracker.
+    Owned
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RowElement {
     pub kind: MapElementKind,
     pub state: RowElementState,
     pub min_count: Option<i32>,
     pub max_count: Option<i32>,
-    pub notes: String
+    pub notes: String,
+    /// Set once the state was picked by hand, the autotracker leaves the icon alone from then on.
+    #[serde(default)]
+    pub manual_override: bool,
+    /// Upgrade level of an owned item, e.g. 2 for the mitt or 1-4 for the sword.
+    #[serde(default)]
+    pub level: u8
 }
 
 pub fn map_element_kind_to_row_element(kind: MapElementKind) -> RowElement {
-    return RowElement {
-       kind: kind,
-       state: RowElementState::NONE,
+    RowElement {
+       kind,
+       state: RowElementState::None,
        min_count: None,
        max_count: None,
-       notes: String::new()
-    };
-}
\ No newline at end of file
+       notes: String::new(),
+       manual_override: false,
+       level: 0
+    }
+}
+
+/// How the autotracker shows a location: crossed once every check is collected, circled while some are left.
+pub fn autotracked_state(status: CheckStatus) -> Option<RowElementState> {
+    match status {
+        CheckStatus::Complete => Some(RowElementState::Crossed),
+        CheckStatus::Partial => Some(RowElementState::Circled),
+        CheckStatus::None => None
+    }
+}
+
+/// The state a row icon is drawn in. Caves and dungeons with several ends show as crossed once
+/// every end is placed on the map, unless a state was picked by hand, and go back as soon as one is removed.
+pub fn shown_state(item: &RowElement, placed: &HashSet<MapElementKind>) -> RowElementState {
+    if item.manual_override || item.state == RowElementState::Owned {
+        return item.state.clone();
+    }
+
+    match registry().connector(item.kind) {
+        Some(connector) if connector.members.iter().all(|kind| placed.contains(kind)) => RowElementState::Crossed,
+        _ => item.state.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placed(kinds: &[MapElementKind]) -> HashSet<MapElementKind> {
+        kinds.iter().copied().collect()
+    }
+
+    #[test]
+    fn crosses_connectors_once_every_end_is_placed() {
+        let middle = map_element_kind_to_row_element(MapElementKind::PARAM);
+        assert_eq!(shown_state(&middle, &placed(&[])), RowElementState::None);
+        assert_eq!(shown_state(&middle, &placed(&[MapElementKind::PARAU, MapElementKind::PARAM])), RowElementState::None);
+
+        let all = [MapElementKind::PARAU, MapElementKind::PARAM, MapElementKind::PARAL];
+        assert_eq!(shown_state(&middle, &placed(&all)), RowElementState::Crossed);
+        let upper = map_element_kind_to_row_element(MapElementKind::PARAU);
+        assert_eq!(shown_state(&upper, &placed(&all)), RowElementState::Crossed);
+
+        // Removing an end takes the cross away again
+        assert_eq!(shown_state(&middle, &placed(&all[..2])), RowElementState::None);
+    }
+
+    #[test]
+    fn keeps_stored_states_unless_the_connector_is_complete() {
+        let circled = RowElement { state: RowElementState::Circled, ..map_element_kind_to_row_element(MapElementKind::SPIRALU) };
+        assert_eq!(shown_state(&circled, &placed(&[MapElementKind::SPIRALU])), RowElementState::Circled);
+        assert_eq!(shown_state(&circled, &placed(&[MapElementKind::SPIRALU, MapElementKind::SPIRALL])), RowElementState::Crossed);
+
+        let manual = RowElement { manual_override: true, ..circled };
+        assert_eq!(shown_state(&manual, &placed(&[MapElementKind::SPIRALU, MapElementKind::SPIRALL])), RowElementState::Circled);
+
+        // Caves with a single way in are never crossed by placing them
+        let spike = map_element_kind_to_row_element(MapElementKind::SPIKE);
+        assert_eq!(shown_state(&spike, &placed(&[MapElementKind::SPIKE])), RowElementState::None);
+    }
+}
@@ -0,0 +1,219 @@
+use eframe::egui::{self};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::map_element;
+use map_element::*;
+
+use crate::row_element;
+use row_element::*;
+
+use crate::layout::ROW_MAX_LENGTH;
+
+pub const SESSION_VERSION: u32 = 1;
+pub const LAST_SESSION_FILE: &str = "last_session.json";
+
+#[derive(Serialize, Deserialize)]
+struct MapElementJson {
+    kind: MapElementKind,
+    x: f32,
+    y: f32,
+    size: Option<[f32; 2]>,
+    checked: bool,
+    #[serde(default)]
+    check: Option<MapElementKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// For placed icons, the index of the linked entrance in `entrances`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entrance: Option<usize>
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionJson {
+    version: u32,
+    entrances: Vec<MapElementJson>,
+    placed_icons: Vec<MapElementJson>,
+    row_icons: Vec<Vec<RowElement>>
+}
+
+/// Everything needed to rebuild the tracker state of `MyApp`.
+pub struct Session {
+    pub entrances: HashMap<Uuid, MapElement>,
+    pub placed_icons: HashMap<Uuid, MapElement>,
+    pub row_icons: [[RowElement; ROW_MAX_LENGTH]; 4]
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    RowCount(usize)
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Io(err) => write!(f, "Unable to access session file: {}", err),
+            SessionError::Json(err) => write!(f, "Session file is not valid: {}", err),
+            SessionError::UnsupportedVersion(version) => write!(f, "Unsupported session version {}", version),
+            SessionError::RowCount(count) => write!(f, "Session has {} rows, expected 4", count)
+        }
+    }
+}
+
+impl From<io::Error> for SessionError {
+    fn from(err: io::Error) -> Self {
+        SessionError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SessionError {
+    fn from(err: serde_json::Error) -> Self {
+        SessionError::Json(err)
+    }
+}
+
+/// Element ids are not saved, so links to entrances are stored as indices into the saved entrance list.
+fn element_to_json(item: &MapElement, entrance_ids: &[Uuid]) -> MapElementJson {
+    MapElementJson {
+        kind: item.kind,
+        x: item.pos.x,
+        y: item.pos.y,
+        size: item.size.map(|size| [size.x, size.y]),
+        checked: item.checked,
+        check: item.check,
+        name: item.name.clone(),
+        entrance: item.entrance.and_then(|id| entrance_ids.iter().position(|entrance| *entrance == id))
+    }
+}
+
+fn json_to_elements(items: Vec<MapElementJson>, entrance_ids: &[Uuid]) -> Vec<MapElement> {
+    items.into_iter().map(|item| MapElement {
+        pos: egui::Pos2::new(item.x, item.y),
+        size: item.size.map(|size| egui::Vec2::new(size[0], size[1])),
+        kind: item.kind,
+        id: Uuid::new_v4(),
+        checked: item.checked,
+        check: item.check,
+        name: item.name,
+        entrance: item.entrance.and_then(|index| entrance_ids.get(index).copied())
+    }).collect()
+}
+
+fn elements_by_id(elements: Vec<MapElement>) -> HashMap<Uuid, MapElement> {
+    elements.into_iter().map(|element| (element.id, element)).collect()
+}
+
+fn json_to_row(row: Vec<RowElement>) -> [RowElement; ROW_MAX_LENGTH] {
+    let mut row = row.into_iter();
+    core::array::from_fn(|_| row.next().unwrap_or_else(|| map_element_kind_to_row_element(MapElementKind::BLANK)))
+}
+
+pub fn to_json(
+    entrances: &HashMap<Uuid, MapElement>,
+    placed_icons: &HashMap<Uuid, MapElement>,
+    row_icons: &[[RowElement; ROW_MAX_LENGTH]; 4]
+) -> String {
+    let entrance_ids: Vec<Uuid> = entrances.keys().copied().collect();
+    let session = SessionJson {
+        version: SESSION_VERSION,
+        entrances: entrance_ids.iter().map(|id| element_to_json(&entrances[id], &[])).collect(),
+        placed_icons: placed_icons.values().map(|item| element_to_json(item, &entrance_ids)).collect(),
+        row_icons: row_icons.iter().map(|row| row.to_vec()).collect()
+    };
+
+    serde_json::to_string_pretty(&session).expect("Session should always serialize")
+}
+
+pub fn from_json(json_data: &str) -> Result<Session, SessionError> {
+    let session: SessionJson = serde_json::from_str(json_data)?;
+    if session.version != SESSION_VERSION {
+        return Err(SessionError::UnsupportedVersion(session.version));
+    }
+    if session.row_icons.len() != 4 {
+        return Err(SessionError::RowCount(session.row_icons.len()));
+    }
+
+    let entrances = json_to_elements(session.entrances, &[]);
+    let entrance_ids: Vec<Uuid> = entrances.iter().map(|entrance| entrance.id).collect();
+    let placed_icons = json_to_elements(session.placed_icons, &entrance_ids);
+
+    let mut rows = session.row_icons.into_iter();
+    Ok(Session {
+        entrances: elements_by_id(entrances),
+        placed_icons: elements_by_id(placed_icons),
+        row_icons: core::array::from_fn(|_| json_to_row(rows.next().unwrap_or_default()))
+    })
+}
+
+pub fn save(path: &Path, json_data: &str) -> Result<(), SessionError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Write next to the target first so a crash mid-write never leaves a truncated session
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json_data)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<Session, SessionError> {
+    let json_data = fs::read_to_string(path)?;
+    from_json(&json_data)
+}
+
+/// Per-user directory for the tracker's own files (settings, last session).
+pub fn data_dir() -> PathBuf {
+    let base = std::env::var_os("APPDATA")
+        .or_else(|| std::env::var_os("XDG_CONFIG_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    base.join("rustrando")
+}
+
+pub fn last_session_path() -> PathBuf {
+    data_dir().join(LAST_SESSION_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(kind: MapElementKind, x: f32, y: f32) -> MapElement {
+        let mut element = map_element_kind_to_map_element(kind);
+        element.pos = egui::Pos2::new(x, y);
+        element
+    }
+
+    #[test]
+    fn keeps_entrance_names_and_links_across_save_and_load() {
+        let mut kings_tomb = element(MapElementKind::ENTRANCE, 500.0, 280.0);
+        kings_tomb.name = Some(String::from("Kings Grave"));
+        let sanctuary = element(MapElementKind::ENTRANCE, 230.0, 160.0);
+        let mut spike = element(MapElementKind::SPIKE, 500.0, 280.0);
+        spike.entrance = Some(kings_tomb.id);
+        let loose = element(MapElementKind::HOOKSHOT, 100.0, 100.0);
+
+        let entrances = elements_by_id(vec![kings_tomb.clone(), sanctuary]);
+        let placed_icons = elements_by_id(vec![spike, loose]);
+        let rows = core::array::from_fn(|_| core::array::from_fn(|_| map_element_kind_to_row_element(MapElementKind::BLANK)));
+        let session = from_json(&to_json(&entrances, &placed_icons, &rows)).expect("Session should load");
+
+        let spike = session.placed_icons.values().find(|icon| icon.kind == MapElementKind::SPIKE).unwrap();
+        let linked = session.entrances.get(&spike.entrance.expect("Link should survive")).unwrap();
+        assert_eq!(linked.pos, kings_tomb.pos);
+        assert_eq!(linked.name.as_deref(), Some("Kings Grave"));
+
+        let loose = session.placed_icons.values().find(|icon| icon.kind == MapElementKind::HOOKSHOT).unwrap();
+        assert_eq!(loose.entrance, None);
+    }
+}
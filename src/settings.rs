@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::autotracker;
+use crate::memory_source::MemorySourceKind;
+use crate::retroarch;
+use crate::session;
+use crate::usb2snes;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub restore_last_session: bool,
+    pub session_path: String,
+    /// Empty means the built-in map.
+    pub map_path: String,
+    /// Collected ITEM markers are hidden instead of dimmed.
+    pub hide_collected_items: bool,
+    pub memory_source: MemorySourceKind,
+    /// usb2snes/SNI websocket URL.
+    pub autotracker_url: String,
+    /// RetroArch network command host and port.
+    pub retroarch_address: String,
+    /// Save file watched by the offline source.
+    pub srm_path: String,
+    pub poll_interval_ms: u64,
+    /// The device picked last time, attached to again without asking.
+    pub autotracker_device: Option<String>,
+    /// Places the icon behind an entrance Link used without asking first.
+    pub auto_place_links: bool,
+    pub show_link_position: bool,
+    /// Lines between the placed ends of caves and dungeons with several entrances.
+    pub show_connectors: bool
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            restore_last_session: true,
+            session_path: String::from("session.json"),
+            map_path: String::new(),
+            hide_collected_items: false,
+            memory_source: MemorySourceKind::Usb2snes,
+            autotracker_url: String::from(usb2snes::DEFAULT_URL),
+            retroarch_address: String::from(retroarch::DEFAULT_ADDRESS),
+            srm_path: String::new(),
+            poll_interval_ms: autotracker::DEFAULT_POLL_INTERVAL.as_millis() as u64,
+            autotracker_device: None,
+            auto_place_links: false,
+            show_link_position: true,
+            show_connectors: true
+        }
+    }
+}
+
+fn settings_path() -> PathBuf {
+    session::data_dir().join(SETTINGS_FILE)
+}
+
+/// Missing or unreadable settings fall back to the defaults rather than blocking startup.
+pub fn load() -> Settings {
+    fs::read_to_string(settings_path())
+        .ok()
+        .and_then(|json_data| serde_json::from_str(&json_data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(settings: &Settings) {
+    let path = settings_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    let json_data = serde_json::to_string_pretty(settings).expect("Settings should always serialize");
+    if let Err(err) = fs::write(&path, json_data) {
+        log::warn!("Unable to save settings to {}: {}", path.display(), err);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::map_element;
+use map_element::*;
+
+/// One bit group in the save data, set once the check has been collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SramCheck {
+    /// Offset from the start of the save data.
+    pub offset: usize,
+    pub mask: u8
+}
+
+/// Every check that makes up one tracker location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SramLocation {
+    pub kind: MapElementKind,
+    pub checks: &'static [SramCheck]
+}
+
+/// Ordered by progress, so a lower status than before means the read went backwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    None,
+    Partial,
+    Complete
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusChange {
+    pub kind: MapElementKind,
+    pub old: CheckStatus,
+    pub new: CheckStatus
+}
+
+/// An inventory slot in the save data. `levels[value]` is the upgrade level a stored value
+/// stands for, with 0 meaning not owned and anything past the end of the list counting as 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InventoryItem {
+    pub kind: MapElementKind,
+    pub offset: usize,
+    pub levels: &'static [u8]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelChange {
+    pub kind: MapElementKind,
+    pub old: u8,
+    pub new: u8
+}
+
+const fn check(offset: usize, mask: u8) -> SramCheck {
+    SramCheck { offset, mask }
+}
+
+const fn location(kind: MapElementKind, checks: &'static [SramCheck]) -> SramLocation {
+    SramLocation { kind, checks }
+}
+
+pub const LOCATIONS: &[SramLocation] = &[
+    location(MapElementKind::SUPERBUNNYU, &[check(0x1F0, 0x30)]),
+    location(MapElementKind::SPIRALU, &[check(0x1FC, 0x10)]),
+    location(MapElementKind::SPECM, &[check(0x1D5, 0x04)]),
+    location(MapElementKind::PARAM, &[check(0x1DE, 0xF0), check(0x1DF, 0x01)]),
+    location(MapElementKind::PARAL, &[check(0x1FE, 0x30)]),
+    location(MapElementKind::HOOKSHOT, &[check(0x078, 0xF0)]),
+    location(MapElementKind::HYPE, &[check(0x23C, 0xF0), check(0x23D, 0x04)]),
+    location(MapElementKind::MIRESHED, &[check(0x21A, 0x30)]),
+    location(MapElementKind::LIBRARY, &[check(0x410, 0x80)]),
+    location(MapElementKind::SICKKID, &[check(0x410, 0x04)]),
+    location(MapElementKind::AGINAH, &[check(0x214, 0x10)]),
+    location(MapElementKind::DAM, &[check(0x216, 0x10)]),
+    location(MapElementKind::ICEROD, &[check(0x240, 0x10)]),
+    location(MapElementKind::HAMMERPEGS, &[check(0x24F, 0x04)]),
+    location(MapElementKind::MMC, &[check(0x246, 0xF0), check(0x247, 0x04)]),
+    location(MapElementKind::WATERFALL, &[check(0x228, 0x30)]),
+    location(MapElementKind::PFAIRY, &[check(0x22C, 0x30)]),
+    location(MapElementKind::MIMIC, &[check(0x218, 0x10)]),
+    location(MapElementKind::CHESTGAME, &[check(0x20D, 0x04)]),
+    location(MapElementKind::CHICKEN, &[check(0x210, 0x10)]),
+    location(MapElementKind::BONK, &[check(0x248, 0x10)]),
+    location(MapElementKind::BREWERY, &[check(0x20C, 0x10)]),
+    location(MapElementKind::CHECKERBOARD, &[check(0x24D, 0x02)]),
+    location(MapElementKind::BLINDS, &[check(0x23A, 0xF0), check(0x23B, 0x01)]),
+    location(MapElementKind::SPIKE, &[check(0x22E, 0x10)]),
+    location(MapElementKind::CAVE45, &[check(0x237, 0x04)]),
+    location(MapElementKind::GYL, &[check(0x237, 0x02)]),
+    location(MapElementKind::CHOUSE, &[check(0x238, 0x10)]),
+    location(MapElementKind::KINGSTOMB, &[check(0x226, 0x10)]),
+    location(MapElementKind::SAHA, &[check(0x20A, 0x70), check(0x410, 0x10)]),
+    location(MapElementKind::POTION, &[check(0x411, 0x20)]),
+    location(MapElementKind::SMITH, &[check(0x411, 0x04)]),
+    location(MapElementKind::POWDER, &[check(0x411, 0x80)]),
+    location(MapElementKind::BOMBOSTABLET, &[check(0x411, 0x02)]),
+    location(MapElementKind::ETHERTABLET, &[check(0x411, 0x01)]),
+    location(MapElementKind::BOTTLEMERCHANT, &[check(0x3C9, 0x02)]),
+    location(MapElementKind::DESERTLEDGE, &[check(0x2B0, 0x40)]),
+    location(MapElementKind::FLOATINGISLAND, &[check(0x285, 0x40)]),
+    location(MapElementKind::HOBO, &[check(0x3C9, 0x01)]),
+    location(MapElementKind::KINGZORA, &[check(0x410, 0x02)]),
+    location(MapElementKind::ZORALEDGE, &[check(0x301, 0x40)]),
+    location(MapElementKind::LAKEHYLIAISLAND, &[check(0x2B5, 0x40)]),
+    location(MapElementKind::PED, &[check(0x300, 0x40)]),
+    location(MapElementKind::MUSHROOM, &[check(0x411, 0x10)]),
+    location(MapElementKind::OLDMAN, &[check(0x410, 0x01)]),
+    location(MapElementKind::SPECROCK, &[check(0x283, 0x40)]),
+    location(MapElementKind::SUNKENTREASURE, &[check(0x2BB, 0x40)]),
+    location(MapElementKind::BUMPERCAVELEDGE, &[check(0x2CA, 0x40)]),
+    location(MapElementKind::MAZERACE, &[check(0x2A8, 0x40)]),
+    location(MapElementKind::DIGGAME, &[check(0x2E8, 0x40)]),
+    location(MapElementKind::STUMPY, &[check(0x410, 0x08)]),
+    location(MapElementKind::FLUTESPOT, &[check(0x2AA, 0x40)]),
+    location(MapElementKind::PURPLECHEST, &[check(0x3C9, 0x10)]),
+    location(MapElementKind::PYRAMID, &[check(0x2DB, 0x40)]),
+    location(MapElementKind::CATFISH, &[check(0x410, 0x20)]),
+];
+
+const fn item(kind: MapElementKind, offset: usize, levels: &'static [u8]) -> InventoryItem {
+    InventoryItem { kind, offset, levels }
+}
+
+const OWNED: &[u8] = &[0, 1];
+
+pub const INVENTORY: &[InventoryItem] = &[
+    // Bow, bow with arrows, silvers, silvers with arrows
+    item(MapElementKind::BOW, 0x340, &[0, 1, 1, 2, 2]),
+    // 1 is the mushroom, which is traded away for the powder
+    item(MapElementKind::POWDER, 0x344, &[0, 0, 1]),
+    item(MapElementKind::FIREROD, 0x345, OWNED),
+    item(MapElementKind::BOMBOS, 0x347, OWNED),
+    item(MapElementKind::LAMP, 0x34A, OWNED),
+    item(MapElementKind::HAMMER, 0x34B, OWNED),
+    item(MapElementKind::SOMARIA, 0x350, OWNED),
+    // 1 is the map scroll the mirror replaces
+    item(MapElementKind::MIRROR, 0x353, &[0, 0, 1]),
+    // Glove, then mitt
+    item(MapElementKind::GLOVE, 0x354, &[0, 1, 2]),
+    item(MapElementKind::BOOTS, 0x355, OWNED),
+    item(MapElementKind::FLIPPERS, 0x356, OWNED),
+    item(MapElementKind::MEARL, 0x357, OWNED),
+    // Fighter, master, tempered and gold sword. 0xFF means swordless
+    item(MapElementKind::SWORD, 0x359, &[0, 1, 2, 3, 4])
+];
+
+impl InventoryItem {
+    pub fn level(&self, data: &[u8]) -> u8 {
+        data.get(self.offset)
+            .and_then(|value| self.levels.get(*value as usize))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+pub fn is_inventory_kind(kind: MapElementKind) -> bool {
+    INVENTORY.iter().any(|item| item.kind == kind)
+}
+
+impl SramLocation {
+    /// Complete once every masked bit is set, partial as soon as any one is, e.g. one chest of a
+    /// multi-chest room. Bytes missing from `data` count as not collected.
+    pub fn status(&self, data: &[u8]) -> CheckStatus {
+        let mut all_checked = true;
+        let mut any_checked = false;
+        for check in self.checks {
+            let value = data.get(check.offset).copied().unwrap_or(0) & check.mask;
+            all_checked = all_checked && value == check.mask;
+            any_checked = any_checked || value != 0;
+        }
+
+        if all_checked {
+            CheckStatus::Complete
+        } else if any_checked {
+            CheckStatus::Partial
+        } else {
+            CheckStatus::None
+        }
+    }
+}
+
+/// Every location whose status differs between two save data buffers.
+pub fn diff(old: &[u8], new: &[u8]) -> Vec<StatusChange> {
+    LOCATIONS.iter()
+        .map(|location| StatusChange { kind: location.kind, old: location.status(old), new: location.status(new) })
+        .filter(|change| change.old != change.new)
+        .collect()
+}
+
+/// Every inventory item whose level differs between two save data buffers.
+pub fn diff_inventory(old: &[u8], new: &[u8]) -> Vec<LevelChange> {
+    INVENTORY.iter()
+        .map(|item| LevelChange { kind: item.kind, old: item.level(old), new: item.level(new) })
+        .filter(|change| change.old != change.new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autotracker::SAVEDATA_SIZE;
+    use crate::autotracker::test_helpers::buffer;
+    use std::path::Path;
+
+    /// Hand-made save data, not a dump from a console: Hookshot Cave, the Dam and the Library
+    /// collected and two of the Paradox Cave chests open.
+    fn synthetic_dump() -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/synthetic_hookshot_dam_library.bin");
+        std::fs::read(path).expect("Snapshot file should load")
+    }
+
+    fn location(kind: MapElementKind) -> &'static SramLocation {
+        LOCATIONS.iter().find(|location| location.kind == kind).expect("Kind should have a location")
+    }
+
+    #[test]
+    fn status_follows_the_masked_bits() {
+        let hookshot = location(MapElementKind::HOOKSHOT);
+        assert_eq!(hookshot.status(&buffer(SAVEDATA_SIZE, &[])), CheckStatus::None);
+        // Bits outside the mask belong to other checks
+        assert_eq!(hookshot.status(&buffer(SAVEDATA_SIZE, &[(0x078, 0x0F)])), CheckStatus::None);
+        assert_eq!(hookshot.status(&buffer(SAVEDATA_SIZE, &[(0x078, 0x10)])), CheckStatus::Partial);
+        assert_eq!(hookshot.status(&buffer(SAVEDATA_SIZE, &[(0x078, 0xF0)])), CheckStatus::Complete);
+        assert_eq!(hookshot.status(&[]), CheckStatus::None);
+    }
+
+    #[test]
+    fn status_needs_every_group_for_complete() {
+        let paradox = location(MapElementKind::PARAM);
+        assert_eq!(paradox.status(&buffer(SAVEDATA_SIZE, &[(0x1DE, 0xF0)])), CheckStatus::Partial);
+        assert_eq!(paradox.status(&buffer(SAVEDATA_SIZE, &[(0x1DF, 0x01)])), CheckStatus::Partial);
+        assert_eq!(paradox.status(&buffer(SAVEDATA_SIZE, &[(0x1DE, 0xF0), (0x1DF, 0x01)])), CheckStatus::Complete);
+    }
+
+    #[test]
+    fn diffs_a_synthetic_dump() {
+        let dump = synthetic_dump();
+        let mut changes = diff(&buffer(SAVEDATA_SIZE, &[]), &dump);
+        changes.sort_by_key(|change| format!("{:?}", change.kind));
+        assert_eq!(changes, vec![
+            StatusChange { kind: MapElementKind::DAM, old: CheckStatus::None, new: CheckStatus::Complete },
+            StatusChange { kind: MapElementKind::HOOKSHOT, old: CheckStatus::None, new: CheckStatus::Complete },
+            StatusChange { kind: MapElementKind::LIBRARY, old: CheckStatus::None, new: CheckStatus::Complete },
+            StatusChange { kind: MapElementKind::PARAM, old: CheckStatus::None, new: CheckStatus::Partial }
+        ]);
+
+        assert!(diff(&dump, &dump).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_partial_progress() {
+        let old = buffer(SAVEDATA_SIZE, &[(0x23A, 0x30)]);
+        let new = buffer(SAVEDATA_SIZE, &[(0x23A, 0xF0), (0x23B, 0x01)]);
+        assert_eq!(diff(&buffer(SAVEDATA_SIZE, &[]), &old), vec![
+            StatusChange { kind: MapElementKind::BLINDS, old: CheckStatus::None, new: CheckStatus::Partial }
+        ]);
+        assert_eq!(diff(&old, &new), vec![
+            StatusChange { kind: MapElementKind::BLINDS, old: CheckStatus::Partial, new: CheckStatus::Complete }
+        ]);
+    }
+
+    #[test]
+    fn diffs_inventory_levels() {
+        let old = buffer(SAVEDATA_SIZE, &[(0x340, 1), (0x344, 1), (0x359, 1)]);
+        let new = buffer(SAVEDATA_SIZE, &[(0x340, 3), (0x344, 2), (0x354, 2), (0x359, 0xFF)]);
+        let changes = diff_inventory(&old, &new);
+        assert_eq!(changes, vec![
+            LevelChange { kind: MapElementKind::BOW, old: 1, new: 2 },
+            // The mushroom is not the powder yet
+            LevelChange { kind: MapElementKind::POWDER, old: 0, new: 1 },
+            LevelChange { kind: MapElementKind::GLOVE, old: 0, new: 2 },
+            // Swordless is stored as 0xFF
+            LevelChange { kind: MapElementKind::SWORD, old: 1, new: 0 }
+        ]);
+        assert!(diff_inventory(&synthetic_dump(), &synthetic_dump()).is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::autotracker::{SAVEDATA_OFFSET, SAVEDATA_SIZE};
+use crate::memory_source::{MemorySource, SourceError};
+
+/// The first save slot starts the file and is laid out like the save data in WRAM.
+const SLOT_OFFSET: usize = 0;
+
+/// The save data region of a `.srm` file, the same bytes the live autotracker reads from WRAM.
+pub fn load_savedata(path: &Path) -> io::Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    if data.len() < SLOT_OFFSET + SAVEDATA_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Save file has {} bytes, expected at least {}", data.len(), SLOT_OFFSET + SAVEDATA_SIZE)
+        ));
+    }
+
+    Ok(data[SLOT_OFFSET..SLOT_OFFSET + SAVEDATA_SIZE].to_vec())
+}
+
+/// A `.srm` save file written by an emulator, re-read on every poll.
+pub struct SrmFileSource {
+    path: PathBuf,
+    savedata: Vec<u8>
+}
+
+impl SrmFileSource {
+    pub fn new(path: &Path) -> SrmFileSource {
+        SrmFileSource { path: path.to_path_buf(), savedata: Vec::new() }
+    }
+
+    /// The whole file is only 8 KB, so it is read every time instead of trusting modification
+    /// times, which are too coarse on some filesystems to tell quick saves apart.
+    fn refresh(&mut self) -> Result<(), SourceError> {
+        match load_savedata(&self.path) {
+            Ok(savedata) => self.savedata = savedata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(SourceError::Closed),
+            // Caught mid-write, the next poll picks up the finished file
+            Err(_) if !self.savedata.is_empty() => (),
+            Err(err) => return Err(SourceError::Protocol(err.to_string()))
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MemorySource for SrmFileSource {
+    async fn connect(&mut self) -> Result<(), SourceError> {
+        if !self.path.is_file() {
+            return Err(SourceError::Connect(format!("Save file {} not found", self.path.display())));
+        }
+
+        self.refresh()
+    }
+
+    async fn devices(&mut self) -> Result<Vec<String>, SourceError> {
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str());
+        Ok(vec![name.to_string_lossy().to_string()])
+    }
+
+    async fn attach(&mut self, _device: &str) -> Result<(), SourceError> {
+        Ok(())
+    }
+
+    async fn read_wram(&mut self, offset: u32, size: usize) -> Result<Vec<u8>, SourceError> {
+        self.refresh()?;
+
+        let start = offset.checked_sub(SAVEDATA_OFFSET).map(|start| start as usize);
+        match start {
+            Some(start) if start + size <= self.savedata.len() => Ok(self.savedata[start..start + size].to_vec()),
+            _ => Err(SourceError::Protocol(format!("only save data is in a save file, not {:x}", offset)))
+        }
+    }
+
+    fn has_game_state(&self) -> bool {
+        false
+    }
+
+    fn disconnect(&mut self) {
+        self.savedata.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autotracker::{Autotracker, AutotrackerStatus};
+    use crate::autotracker::test_helpers::{buffer, wait_for_checks};
+    use crate::map_element::MapElementKind;
+    use crate::sram::{CheckStatus, StatusChange};
+    use std::time::Duration;
+
+    /// Full size of an ALttP save file.
+    const SRM_SIZE: usize = 0x2000;
+
+    fn write_srm(path: &Path, bits: &[(usize, u8)]) {
+        let bits: Vec<(usize, u8)> = bits.iter().map(|(offset, value)| (SLOT_OFFSET + offset, *value)).collect();
+        fs::write(path, buffer(SRM_SIZE, &bits)).expect("Unable to write save file");
+    }
+
+    #[test]
+    fn rereads_the_save_file_when_it_changes() {
+        let dir = std::env::temp_dir().join(format!("rustrando-srm-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("race.srm");
+        write_srm(&path, &[(0x078, 0xF0)]);
+
+        let source = Box::new(SrmFileSource::new(&path));
+        let mut tracker = Autotracker::start(source, Duration::from_millis(20), None, || ()).expect("Autotracker should start");
+
+        let changes = wait_for_checks(&mut tracker);
+        assert_eq!(changes, vec![StatusChange { kind: MapElementKind::HOOKSHOT, old: CheckStatus::None, new: CheckStatus::Complete }]);
+        assert_eq!(tracker.info().status, AutotrackerStatus::Tracking);
+        assert_eq!(tracker.info().device.as_deref(), Some("race.srm"));
+
+        // Save and quit writes the file again with more progress
+        write_srm(&path, &[(0x078, 0xF0), (0x410, 0x80)]);
+        let changes = wait_for_checks(&mut tracker);
+        assert_eq!(changes, vec![StatusChange { kind: MapElementKind::LIBRARY, old: CheckStatus::None, new: CheckStatus::Complete }]);
+
+        drop(tracker);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_files_too_small_to_be_saves() {
+        let dir = std::env::temp_dir().join(format!("rustrando-srm-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("short.srm");
+        fs::write(&path, [0; 16]).unwrap();
+
+        assert_eq!(load_savedata(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,190 @@
+use async_trait::async_trait;
+use ezsockets::client::ClientCloseMode;
+use ezsockets::ClientConfig;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::memory_source::{MemorySource, SourceError};
+
+pub const DEFAULT_URL: &str = "ws://localhost:8080";
+/// Where WRAM starts in the usb2snes address space.
+pub const WRAM_START: u32 = 0xF50000;
+
+/// SD2SNES reads can stall for a while, anything longer means the device is gone.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Usb2SnesRequest {
+    opcode: &'static str,
+    space: &'static str,
+    operands: Vec<String>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Usb2SnesResponse {
+    results: Vec<String>
+}
+
+fn request(opcode: &'static str, operands: Vec<String>) -> String {
+    let request = Usb2SnesRequest { opcode, space: "SNES", operands };
+    serde_json::to_string(&request).expect("Requests should always serialize")
+}
+
+fn read_request(address: u32, size: usize) -> String {
+    request("GetAddress", vec![format!("{:x}", address), format!("{:x}", size)])
+}
+
+/// What the websocket callbacks hand over to the source.
+enum Incoming {
+    Connected,
+    Failed(String),
+    Text(String),
+    Binary(Vec<u8>),
+    Closed
+}
+
+struct Usb2SnesHandler {
+    incoming: mpsc::UnboundedSender<Incoming>
+}
+
+impl Usb2SnesHandler {
+    fn forward(&self, message: Incoming) {
+        // Nobody left to read it once the source has disconnected
+        let _ = self.incoming.send(message);
+    }
+}
+
+#[async_trait]
+impl ezsockets::ClientExt for Usb2SnesHandler {
+    type Call = ();
+
+    async fn on_connect(&mut self) -> Result<(), ezsockets::Error> {
+        self.forward(Incoming::Connected);
+        Ok(())
+    }
+
+    async fn on_text(&mut self, text: String) -> Result<(), ezsockets::Error> {
+        self.forward(Incoming::Text(text));
+        Ok(())
+    }
+
+    async fn on_binary(&mut self, bytes: Vec<u8>) -> Result<(), ezsockets::Error> {
+        self.forward(Incoming::Binary(bytes));
+        Ok(())
+    }
+
+    async fn on_call(&mut self, _call: ()) -> Result<(), ezsockets::Error> {
+        Ok(())
+    }
+
+    // Reconnecting is up to the autotracker, which does it the same way for every source
+    async fn on_connect_fail(&mut self, error: ezsockets::WSError) -> Result<ClientCloseMode, ezsockets::Error> {
+        self.forward(Incoming::Failed(error.to_string()));
+        Ok(ClientCloseMode::Close)
+    }
+
+    async fn on_close(&mut self, _frame: Option<ezsockets::CloseFrame>) -> Result<ClientCloseMode, ezsockets::Error> {
+        self.forward(Incoming::Closed);
+        Ok(ClientCloseMode::Close)
+    }
+
+    async fn on_disconnect(&mut self) -> Result<ClientCloseMode, ezsockets::Error> {
+        self.forward(Incoming::Closed);
+        Ok(ClientCloseMode::Close)
+    }
+}
+
+/// A usb2snes/SNI websocket server, covering SD2SNES/FXPak hardware and emulators with a usb2snes bridge.
+pub struct Usb2SnesSource {
+    url: url::Url,
+    client: Option<ezsockets::Client<Usb2SnesHandler>>,
+    incoming: Option<mpsc::UnboundedReceiver<Incoming>>
+}
+
+impl Usb2SnesSource {
+    pub fn new(url: &str) -> Result<Usb2SnesSource, String> {
+        let url = url::Url::parse(url).map_err(|err| format!("Invalid autotracker URL {}: {}", url, err))?;
+        Ok(Usb2SnesSource { url, client: None, incoming: None })
+    }
+
+    fn send(&self, text: String) -> Result<(), SourceError> {
+        let client = self.client.as_ref().ok_or(SourceError::Closed)?;
+        client.text(text).map_err(|_| SourceError::Closed)?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<Incoming, SourceError> {
+        let incoming = self.incoming.as_mut().ok_or(SourceError::Closed)?;
+        match tokio::time::timeout(READ_TIMEOUT, incoming.recv()).await {
+            Ok(Some(Incoming::Closed)) | Ok(None) => Err(SourceError::Closed),
+            Ok(Some(message)) => Ok(message),
+            Err(_) => Err(SourceError::Timeout)
+        }
+    }
+}
+
+#[async_trait]
+impl MemorySource for Usb2SnesSource {
+    async fn connect(&mut self) -> Result<(), SourceError> {
+        self.disconnect();
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let config = ClientConfig::new(self.url.clone()).max_initial_connect_attempts(1);
+        let (client, _future) = ezsockets::connect(|_| Usb2SnesHandler { incoming: sender }, config).await;
+        self.client = Some(client);
+        self.incoming = Some(receiver);
+
+        match self.receive().await? {
+            Incoming::Connected => Ok(()),
+            Incoming::Failed(err) => Err(SourceError::Connect(err)),
+            _ => Err(SourceError::Protocol(String::from("message before connecting")))
+        }
+    }
+
+    async fn devices(&mut self) -> Result<Vec<String>, SourceError> {
+        self.send(request("DeviceList", Vec::new()))?;
+        loop {
+            if let Incoming::Text(text) = self.receive().await? {
+                let response: Usb2SnesResponse = serde_json::from_str(&text)
+                    .map_err(|err| SourceError::Protocol(err.to_string()))?;
+                return Ok(response.results);
+            }
+        }
+    }
+
+    async fn attach(&mut self, device: &str) -> Result<(), SourceError> {
+        // Attach has no answer, a bad device only shows once reads stop coming back
+        self.send(request("Attach", vec![device.to_string()]))
+    }
+
+    async fn read_wram(&mut self, offset: u32, size: usize) -> Result<Vec<u8>, SourceError> {
+        self.send(read_request(WRAM_START + offset, size))?;
+
+        // Larger reads can arrive split over several messages
+        let mut buffer: Vec<u8> = Vec::with_capacity(size);
+        while buffer.len() < size {
+            if let Incoming::Binary(bytes) = self.receive().await? {
+                buffer.extend_from_slice(&bytes);
+            }
+        }
+
+        buffer.truncate(size);
+        Ok(buffer)
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(client) = self.client.take() {
+            let _ = client.close(None);
+        }
+        self.incoming = None;
+    }
+}
+
+impl Drop for Usb2SnesSource {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
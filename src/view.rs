@@ -0,0 +1,152 @@
+use eframe::egui::{Pos2, Rect, Vec2};
+
+use crate::layout;
+
+pub const MIN_ZOOM: f32 = 1.0;
+pub const MAX_ZOOM: f32 = 8.0;
+/// Zoom used when jumping to a single marker, unless already zoomed in further.
+pub const FOCUS_ZOOM: f32 = 3.0;
+
+/// Where each part of the tracker goes on screen this frame, scaled to fit the window.
+pub struct ScreenLayout {
+    pub scale: f32,
+    pub map_rect: Rect,
+    pub rows_origin: Pos2,
+    pub controls_rect: Rect
+}
+
+impl ScreenLayout {
+    pub fn new(available: Rect) -> Self {
+        let scale = (available.width() / layout::DESIGN_WIDTH).min(available.height() / layout::DESIGN_HEIGHT);
+        let map_rect = Rect::from_min_size(
+            available.min,
+            Vec2::new(2.0 * layout::MAP_SIZE, layout::MAP_SIZE) * scale
+        );
+        let controls_rect = Rect::from_min_max(
+            available.min + Vec2::new(layout::CONTROLS_X_START * scale, map_rect.height() + 10.0 * scale),
+            available.max
+        );
+
+        ScreenLayout {
+            scale,
+            map_rect,
+            rows_origin: available.min + Vec2::new(layout::ROW_X_START, layout::ROW_Y_START) * scale,
+            controls_rect
+        }
+    }
+}
+
+/// Zoom and pan over both maps. Positions on the map are kept in map-space,
+/// where each world is `MAP_SIZE` wide, and only converted to screen-space for drawing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapView {
+    pub zoom: f32,
+    /// Map-space point shown at the top left corner of the map area.
+    pub pan: Vec2
+}
+
+impl Default for MapView {
+    fn default() -> Self {
+        MapView { zoom: MIN_ZOOM, pan: Vec2::ZERO }
+    }
+}
+
+impl MapView {
+    /// Screen pixels per map-space unit.
+    pub fn scale(&self, screen: &ScreenLayout) -> f32 {
+        screen.scale * self.zoom
+    }
+
+    pub fn map_to_screen(&self, screen: &ScreenLayout, pos: Pos2) -> Pos2 {
+        screen.map_rect.min + (pos.to_vec2() - self.pan) * self.scale(screen)
+    }
+
+    pub fn screen_to_map(&self, screen: &ScreenLayout, pos: Pos2) -> Pos2 {
+        (self.pan + (pos - screen.map_rect.min) / self.scale(screen)).to_pos2()
+    }
+
+    pub fn map_rect_to_screen(&self, screen: &ScreenLayout, rect: Rect) -> Rect {
+        Rect::from_min_max(self.map_to_screen(screen, rect.min), self.map_to_screen(screen, rect.max))
+    }
+
+    /// Zooms by `factor` while keeping the map point under `anchor` in place.
+    pub fn zoom_at(&mut self, screen: &ScreenLayout, anchor: Pos2, factor: f32) {
+        let anchor_map = self.screen_to_map(screen, anchor);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan = anchor_map.to_vec2() - (anchor - screen.map_rect.min) / self.scale(screen);
+        self.clamp_pan();
+    }
+
+    pub fn pan_by(&mut self, screen: &ScreenLayout, screen_delta: Vec2) {
+        self.pan -= screen_delta / self.scale(screen);
+        self.clamp_pan();
+    }
+
+    /// Zooms in on `pos` and puts it in the middle of the map area, as far as the maps allow.
+    pub fn focus_on(&mut self, pos: Pos2) {
+        self.zoom = self.zoom.max(FOCUS_ZOOM);
+        let visible = Vec2::new(2.0 * layout::MAP_SIZE, layout::MAP_SIZE) / self.zoom;
+        self.pan = pos.to_vec2() - visible / 2.0;
+        self.clamp_pan();
+    }
+
+    /// Keeps the visible window inside the maps.
+    fn clamp_pan(&mut self) {
+        let visible = Vec2::new(2.0 * layout::MAP_SIZE, layout::MAP_SIZE) / self.zoom;
+        self.pan.x = self.pan.x.clamp(0.0, 2.0 * layout::MAP_SIZE - visible.x);
+        self.pan.y = self.pan.y.clamp(0.0, layout::MAP_SIZE - visible.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen() -> ScreenLayout {
+        ScreenLayout::new(Rect::from_min_size(Pos2::new(8.0, 8.0), Vec2::new(layout::DESIGN_WIDTH, layout::DESIGN_HEIGHT) * 1.5))
+    }
+
+    fn assert_near(a: Pos2, b: Pos2) {
+        assert!(a.distance(b) < 0.01, "{:?} is not {:?}", a, b);
+    }
+
+    #[test]
+    fn map_and_screen_positions_round_trip() {
+        let screen = screen();
+        let view = MapView { zoom: 2.5, pan: Vec2::new(100.0, 50.0) };
+        for pos in [Pos2::new(0.0, 0.0), Pos2::new(123.4, 567.8), Pos2::new(layout::MAP_SIZE + 10.0, 300.0)] {
+            assert_near(view.screen_to_map(&screen, view.map_to_screen(&screen, pos)), pos);
+        }
+        assert_near(view.map_to_screen(&screen, Pos2::new(100.0, 50.0)), screen.map_rect.min);
+    }
+
+    #[test]
+    fn zooming_keeps_the_anchor_in_place() {
+        let screen = screen();
+        let mut view = MapView::default();
+        let anchor = screen.map_rect.min + Vec2::new(300.0, 200.0);
+        let under_anchor = view.screen_to_map(&screen, anchor);
+
+        view.zoom_at(&screen, anchor, 2.0);
+        assert_eq!(view.zoom, 2.0);
+        assert_near(view.screen_to_map(&screen, anchor), under_anchor);
+
+        view.zoom_at(&screen, anchor, 100.0);
+        assert_eq!(view.zoom, MAX_ZOOM);
+        assert_near(view.screen_to_map(&screen, anchor), under_anchor);
+    }
+
+    #[test]
+    fn pan_stays_inside_the_maps() {
+        let screen = screen();
+        let mut view = MapView::default();
+        view.pan_by(&screen, Vec2::new(-50.0, -50.0));
+        assert_eq!(view.pan, Vec2::ZERO);
+
+        view.zoom = 2.0;
+        view.pan_by(&screen, Vec2::new(-1.0e6, -1.0e6));
+        assert_eq!(view.pan, Vec2::new(layout::MAP_SIZE, layout::MAP_SIZE / 2.0));
+        view.pan_by(&screen, Vec2::new(1.0e6, 1.0e6));
+        assert_eq!(view.pan, Vec2::ZERO);
+    }
+}